@@ -1,3 +1,4 @@
+use anychain_solana::{ui_amount_to_base, SolanaCluster};
 use bip39::{Mnemonic, Seed};
 use solana_rpc_client::rpc_client::RpcClient;
 use solana_sdk::{
@@ -141,7 +142,7 @@ pub fn transfer_spl_token(
 }
 
 fn main() -> anyhow::Result<()> {
-    let rpc_client = RpcClient::new("https://api.testnet.solana.com".to_string());
+    let rpc_client = RpcClient::new(SolanaCluster::Testnet.rpc_url().to_string());
     let alice_keypair = Keypair::from_bytes(&[
         41, 196, 252, 146, 80, 100, 13, 46, 69, 89, 172, 157, 224, 135, 23, 62, 54, 65, 52, 68, 14,
         50, 112, 112, 156, 210, 24, 236, 139, 169, 38, 63, 205, 66, 112, 255, 116, 177, 79, 182,
@@ -269,7 +270,7 @@ fn main() -> anyhow::Result<()> {
     )
     */
 
-    let amount: u64 = 1; // Decimals 6
+    let amount = ui_amount_to_base("0.000001", 6)?;
     transfer_spl_token(
         &rpc_client,
         &alice_keypair,
@@ -1,29 +1,147 @@
 use {
-    crate::{format::SolanaFormat, public_key::SolanaPublicKey},
+    crate::{
+        format::SolanaFormat, public_key::SolanaPublicKey, ASSOCIATED_TOKEN_PROGRAM_ID,
+        COMPUTE_BUDGET_PROGRAM_ID, MEMO_PROGRAM_ID, SPL_TOKEN_PROGRAM_ID, SYSTEM_PROGRAM_ID,
+        TOKEN_2022_PROGRAM_ID,
+    },
     anychain_core::{Address, AddressError, PublicKey, PublicKeyError},
     core::{
         fmt::{Display, Formatter, Result as FmtResult},
         str::FromStr,
     },
-    curve25519_dalek::Scalar,
+    curve25519_dalek::{edwards::CompressedEdwardsY, Scalar},
     ed25519_dalek::PUBLIC_KEY_LENGTH,
+    serde::{Deserialize, Serialize},
     solana_sdk::pubkey::Pubkey,
-    spl_associated_token_account::get_associated_token_address,
+    spl_associated_token_account::get_associated_token_address_with_program_id,
 };
 
 /// Represents a Solana address
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct SolanaAddress(pub String);
 
 impl SolanaAddress {
+    /// Shadows `Address::from_secret_key` to deprecate the raw scalar,
+    /// generic code over the trait is unaffected
+    #[deprecated(note = "use `SolanaSecretKey::address`, which zeroizes the secret")]
+    pub fn from_secret_key(
+        secret_key: &Scalar,
+        format: &SolanaFormat,
+    ) -> Result<Self, AddressError> {
+        <Self as Address>::from_secret_key(secret_key, format)
+    }
+
     pub fn associated_token_address(&self, token: String) -> Result<String, AddressError> {
+        self.associated_token_address_with_format(token, &SolanaFormat::Standard)
+    }
+
+    /// Derives the associated token account for `token` owned by the token
+    /// program of `format`
+    pub fn associated_token_address_with_format(
+        &self,
+        token: String,
+        format: &SolanaFormat,
+    ) -> Result<String, AddressError> {
         let address =
             Pubkey::from_str(&self.0).map_err(|e| AddressError::Message(format!("{}", e)))?;
         let token =
             Pubkey::from_str(&token).map_err(|e| AddressError::Message(format!("{}", e)))?;
-        let associated_token_address = get_associated_token_address(&address, &token);
+        let associated_token_address = get_associated_token_address_with_program_id(
+            &address,
+            &token,
+            &format.token_program_id(),
+        );
         Ok(associated_token_address.to_string())
     }
+
+    /// Derives the associated token accounts for each of `mints` under the
+    /// token program of `format`, returned as (mint, associated token
+    /// account) pairs in order
+    pub fn associated_token_addresses(
+        &self,
+        mints: &[String],
+        format: &SolanaFormat,
+    ) -> Result<Vec<(String, String)>, AddressError> {
+        mints
+            .iter()
+            .map(|mint| {
+                let address = self.associated_token_address_with_format(mint.clone(), format)?;
+                Ok((mint.clone(), address))
+            })
+            .collect()
+    }
+
+    /// Abbreviates the address to its first `prefix` and last `suffix`
+    /// characters around an ellipsis, e.g. `DPCG…x4RG`. Returns the whole
+    /// address if it is not longer than both together.
+    pub fn shorten(&self, prefix: usize, suffix: usize) -> String {
+        let chars: Vec<char> = self.0.chars().collect();
+        if prefix.saturating_add(suffix) >= chars.len() {
+            return self.0.clone();
+        }
+        let head: String = chars[..prefix].iter().collect();
+        let tail: String = chars[chars.len() - suffix..].iter().collect();
+        format!("{}…{}", head, tail)
+    }
+
+    /// Returns true if the address is a point on the ed25519 curve, i.e. it
+    /// can be controlled by a private key. Program derived addresses (PDAs),
+    /// such as associated token accounts, are intentionally off-curve.
+    pub fn is_on_curve(&self) -> bool {
+        match bs58::decode(&self.0).into_vec() {
+            Ok(bytes) if bytes.len() == PUBLIC_KEY_LENGTH => {
+                let mut buffer = [0u8; PUBLIC_KEY_LENGTH];
+                buffer.copy_from_slice(&bytes);
+                CompressedEdwardsY(buffer).decompress().is_some()
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns true if the address is valid and on the ed25519 curve, which
+    /// excludes program derived addresses that cannot sign.
+    pub fn is_valid_wallet_address(address: &str) -> bool {
+        match Self::from_str(address) {
+            Ok(address) => address.is_on_curve(),
+            Err(_) => false,
+        }
+    }
+
+    /// The system program
+    pub fn system_program() -> Self {
+        SolanaAddress(SYSTEM_PROGRAM_ID.to_string())
+    }
+
+    /// The SPL token program
+    pub fn token_program() -> Self {
+        SolanaAddress(SPL_TOKEN_PROGRAM_ID.to_string())
+    }
+
+    /// The SPL Token-2022 program
+    pub fn token_2022_program() -> Self {
+        SolanaAddress(TOKEN_2022_PROGRAM_ID.to_string())
+    }
+
+    /// The SPL associated token account program
+    pub fn associated_token_program() -> Self {
+        SolanaAddress(ASSOCIATED_TOKEN_PROGRAM_ID.to_string())
+    }
+
+    /// The SPL memo program (v2)
+    pub fn memo_program() -> Self {
+        SolanaAddress(MEMO_PROGRAM_ID.to_string())
+    }
+
+    /// The compute budget program
+    pub fn compute_budget_program() -> Self {
+        SolanaAddress(COMPUTE_BUDGET_PROGRAM_ID.to_string())
+    }
+
+    /// Returns true if the address starts with the base58 `prefix`, the
+    /// check of a vanity address grinder loop
+    pub fn has_prefix(&self, prefix: &str) -> bool {
+        self.0.starts_with(prefix)
+    }
 }
 
 impl Address for SolanaAddress {
@@ -31,19 +149,25 @@ impl Address for SolanaAddress {
     type Format = SolanaFormat;
     type PublicKey = SolanaPublicKey;
 
+    /// Prefer `SolanaSecretKey::address`, which zeroizes the secret
     fn from_secret_key(
         secret_key: &Self::SecretKey,
         format: &Self::Format,
     ) -> Result<Self, AddressError> {
-        Self::PublicKey::from_secret_key(secret_key).to_address(format)
+        <Self::PublicKey as PublicKey>::from_secret_key(secret_key).to_address(format)
     }
 
     fn from_public_key(
         public_key: &Self::PublicKey,
-        _: &Self::Format,
+        format: &Self::Format,
     ) -> Result<Self, AddressError> {
-        let address = bs58::encode(public_key.0.to_bytes()).into_string();
-        Ok(Self(address))
+        let address = Self(bs58::encode(public_key.0.to_bytes()).into_string());
+        match format {
+            SolanaFormat::AssociatedToken { mint, .. } => address
+                .associated_token_address_with_format(mint.clone(), format)
+                .map(Self),
+            SolanaFormat::Standard | SolanaFormat::Token2022 => Ok(address),
+        }
     }
 
     fn is_valid(address: &str) -> bool {
@@ -56,7 +180,9 @@ impl FromStr for SolanaAddress {
 
     fn from_str(addr: &str) -> Result<Self, Self::Err> {
         // Check if the address is valid
-        if addr.len() > crate::public_key::MAX_BASE58_LEN {
+        if !(crate::public_key::MIN_BASE58_LEN..=crate::public_key::MAX_BASE58_LEN)
+            .contains(&addr.len())
+        {
             return Err(AddressError::InvalidCharacterLength(addr.len()));
         }
         let pubkey_vec = bs58::decode(addr)
@@ -65,14 +191,31 @@ impl FromStr for SolanaAddress {
         if pubkey_vec.len() != PUBLIC_KEY_LENGTH {
             return Err(AddressError::InvalidAddress(addr.to_string()));
         }
-        let buffer: [u8; PUBLIC_KEY_LENGTH] = pubkey_vec.as_slice().try_into().unwrap();
-        let _ = ed25519_dalek::PublicKey::from_bytes(&buffer)
-            .map_err(|error| AddressError::Message(error.to_string()))?;
-
+        // Any 32 bytes are an address, program derived ones are off-curve,
+        // see `is_valid_wallet_address`
         Ok(Self(addr.to_string()))
     }
 }
 
+impl TryFrom<&[u8]> for SolanaAddress {
+    type Error = AddressError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        if bytes.len() != PUBLIC_KEY_LENGTH {
+            return Err(AddressError::InvalidByteLength(bytes.len()));
+        }
+        Ok(Self(bs58::encode(bytes).into_string()))
+    }
+}
+
+impl TryFrom<[u8; PUBLIC_KEY_LENGTH]> for SolanaAddress {
+    type Error = AddressError;
+
+    fn try_from(bytes: [u8; PUBLIC_KEY_LENGTH]) -> Result<Self, Self::Error> {
+        Self::try_from(bytes.as_slice())
+    }
+}
+
 impl Display for SolanaAddress {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         write!(f, "{}", self.0)
@@ -82,9 +225,11 @@ impl Display for SolanaAddress {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::format::SolanaTokenProgram;
     use ed25519_dalek::{KEYPAIR_LENGTH, PUBLIC_KEY_LENGTH, SECRET_KEY_LENGTH};
 
     #[test]
+    #[allow(deprecated)]
     fn test_address_alice() {
         let keypair_bytes: [u8; KEYPAIR_LENGTH] = [
             41, 196, 252, 146, 80, 100, 13, 46, 69, 89, 172, 157, 224, 135, 23, 62, 54, 65, 52, 68,
@@ -107,6 +252,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(deprecated)]
     fn test_address_bob() {
         let keypair_bytes: [u8; KEYPAIR_LENGTH] = [
             47, 232, 53, 167, 54, 186, 162, 109, 156, 250, 166, 187, 29, 118, 132, 137, 28, 228,
@@ -127,4 +273,212 @@ mod tests {
             address.to_string()
         );
     }
+
+    #[test]
+    fn test_address_from_str_invalid_length() {
+        for addr in ["", "D", "DPCG5xKuxK3NCL8FTn8h2vFharp9MbYmUZeSA2eLx4RGD"] {
+            assert!(matches!(
+                SolanaAddress::from_str(addr),
+                Err(AddressError::InvalidCharacterLength(len)) if len == addr.len()
+            ));
+        }
+        assert!(!SolanaAddress::is_valid(""));
+    }
+
+    #[test]
+    fn test_has_prefix() {
+        let address = SolanaAddress("DPCG5xKuxK3NCL8FTn8h2vFharp9MbYmUZeSA2eLx4RG".to_string());
+        assert!(address.has_prefix(""));
+        assert!(address.has_prefix("DPCG"));
+        assert!(address.has_prefix(&address.0));
+        assert!(!address.has_prefix("dpcg"));
+        assert!(!address.has_prefix("PCG"));
+        assert!(!address.has_prefix("DPCG5xKuxK3NCL8FTn8h2vFharp9MbYmUZeSA2eLx4RGX"));
+    }
+
+    #[test]
+    fn test_associated_token_address_formats() {
+        let owner = SolanaAddress("DPCG5xKuxK3NCL8FTn8h2vFharp9MbYmUZeSA2eLx4RG".to_string());
+        let mint = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v".to_string();
+        let standard = owner
+            .associated_token_address_with_format(mint.clone(), &SolanaFormat::Standard)
+            .unwrap();
+        assert_eq!(
+            standard,
+            owner.associated_token_address(mint.clone()).unwrap()
+        );
+        assert_eq!(standard, "9h5MuA25tXEQqFGsTsMGECe7bRj2d75RU2HoDKGAyNcG");
+
+        let token_2022 = owner
+            .associated_token_address_with_format(mint.clone(), &SolanaFormat::Token2022)
+            .unwrap();
+        let expected = Pubkey::find_program_address(
+            &[
+                Pubkey::from_str(&owner.0).unwrap().as_ref(),
+                TOKEN_2022_PROGRAM_ID.as_ref(),
+                Pubkey::from_str(&mint).unwrap().as_ref(),
+            ],
+            &ASSOCIATED_TOKEN_PROGRAM_ID,
+        )
+        .0;
+        assert_eq!(token_2022, expected.to_string());
+        assert_ne!(token_2022, standard);
+    }
+
+    #[test]
+    fn test_associated_token_format() {
+        let public_key =
+            SolanaPublicKey::from_str("DPCG5xKuxK3NCL8FTn8h2vFharp9MbYmUZeSA2eLx4RG").unwrap();
+        let wallet = SolanaAddress::from_public_key(&public_key, &SolanaFormat::Standard).unwrap();
+        assert_eq!(
+            wallet,
+            SolanaAddress::from_public_key(&public_key, &SolanaFormat::Token2022).unwrap()
+        );
+
+        let mint = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v".to_string();
+        for (program, wallet_format) in [
+            (SolanaTokenProgram::Spl, SolanaFormat::Standard),
+            (SolanaTokenProgram::Token2022, SolanaFormat::Token2022),
+        ] {
+            let format = SolanaFormat::AssociatedToken {
+                mint: mint.clone(),
+                program,
+            };
+            let address = SolanaAddress::from_public_key(&public_key, &format).unwrap();
+            assert_eq!(
+                address.0,
+                wallet
+                    .associated_token_address_with_format(mint.clone(), &wallet_format)
+                    .unwrap()
+            );
+            // The off-curve token account still parses as an address
+            assert_eq!(SolanaAddress::from_str(&address.0).unwrap(), address);
+            assert!(SolanaAddress::is_valid(&address.0));
+        }
+
+        let format = SolanaFormat::AssociatedToken {
+            mint: "invalid".to_string(),
+            program: SolanaTokenProgram::Spl,
+        };
+        assert!(SolanaAddress::from_public_key(&public_key, &format).is_err());
+    }
+
+    #[test]
+    fn test_shorten() {
+        let address = SolanaAddress("DPCG5xKuxK3NCL8FTn8h2vFharp9MbYmUZeSA2eLx4RG".to_string());
+        assert_eq!(address.shorten(4, 4), "DPCG…x4RG");
+        assert_eq!(address.shorten(0, 3), "…4RG");
+        assert_eq!(address.shorten(22, 22), address.0);
+        assert_eq!(address.shorten(usize::MAX, 1), address.0);
+
+        let address = SolanaAddress("1111".to_string());
+        assert_eq!(address.shorten(2, 2), "1111");
+        assert_eq!(address.shorten(1, 1), "1…1");
+    }
+
+    #[test]
+    fn test_associated_token_addresses() {
+        let owner = SolanaAddress("DPCG5xKuxK3NCL8FTn8h2vFharp9MbYmUZeSA2eLx4RG".to_string());
+        let mints = vec![
+            "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v".to_string(),
+            "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB".to_string(),
+        ];
+        let pairs = owner
+            .associated_token_addresses(&mints, &SolanaFormat::Standard)
+            .unwrap();
+        assert_eq!(pairs.len(), 2);
+        for (pair, mint) in pairs.iter().zip(&mints) {
+            assert_eq!(&pair.0, mint);
+            assert_eq!(
+                pair.1,
+                owner.associated_token_address(mint.clone()).unwrap()
+            );
+        }
+        assert_eq!(pairs[0].1, "9h5MuA25tXEQqFGsTsMGECe7bRj2d75RU2HoDKGAyNcG");
+
+        let pairs = owner
+            .associated_token_addresses(&mints, &SolanaFormat::Token2022)
+            .unwrap();
+        for (pair, mint) in pairs.iter().zip(&mints) {
+            assert_eq!(
+                pair.1,
+                owner
+                    .associated_token_address_with_format(mint.clone(), &SolanaFormat::Token2022)
+                    .unwrap()
+            );
+        }
+        assert_ne!(pairs[0].1, "9h5MuA25tXEQqFGsTsMGECe7bRj2d75RU2HoDKGAyNcG");
+
+        assert!(owner
+            .associated_token_addresses(&["invalid".to_string()], &SolanaFormat::Standard)
+            .is_err());
+    }
+
+    #[test]
+    fn test_program_addresses() {
+        let programs = [
+            (
+                SolanaAddress::system_program(),
+                "11111111111111111111111111111111",
+            ),
+            (
+                SolanaAddress::token_program(),
+                "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA",
+            ),
+            (
+                SolanaAddress::token_2022_program(),
+                "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb",
+            ),
+            (
+                SolanaAddress::associated_token_program(),
+                "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL",
+            ),
+            (
+                SolanaAddress::memo_program(),
+                "MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr",
+            ),
+            (
+                SolanaAddress::compute_budget_program(),
+                "ComputeBudget111111111111111111111111111111",
+            ),
+        ];
+        for (address, id) in programs {
+            assert_eq!(address.0, id);
+        }
+    }
+
+    #[test]
+    fn test_is_on_curve() {
+        let wallet =
+            SolanaAddress::from_str("DPCG5xKuxK3NCL8FTn8h2vFharp9MbYmUZeSA2eLx4RG").unwrap();
+        assert!(wallet.is_on_curve());
+        assert!(SolanaAddress::is_valid_wallet_address(&wallet.0));
+
+        let ata = wallet
+            .associated_token_address("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v".to_string())
+            .unwrap();
+        let ata = SolanaAddress(ata);
+        assert!(!ata.is_on_curve());
+        // A program derived address is an address, but not a wallet's
+        assert_eq!(SolanaAddress::from_str(&ata.0).unwrap(), ata);
+        assert!(SolanaAddress::is_valid(&ata.0));
+        assert!(!SolanaAddress::is_valid_wallet_address(&ata.0));
+    }
+
+    #[test]
+    fn test_address_try_from_bytes() {
+        let addr = "DPCG5xKuxK3NCL8FTn8h2vFharp9MbYmUZeSA2eLx4RG";
+        let bytes: [u8; PUBLIC_KEY_LENGTH] =
+            bs58::decode(addr).into_vec().unwrap().try_into().unwrap();
+
+        assert_eq!(SolanaAddress::try_from(bytes).unwrap().to_string(), addr);
+        assert_eq!(
+            SolanaAddress::try_from(&bytes[..]).unwrap().to_string(),
+            addr
+        );
+        assert!(matches!(
+            SolanaAddress::try_from(&[0u8; 33][..]),
+            Err(AddressError::InvalidByteLength(33))
+        ));
+    }
 }
@@ -1,7 +1,8 @@
 //! Definitions for the native SOL token and its fractional lamports.
 
 use {
-    anychain_core::{to_basic_unit_u64, Amount, AmountError},
+    crate::SolanaError,
+    anychain_core::{to_basic_unit_u64, Amount, AmountError, TransactionError},
     core::fmt,
     serde::{Deserialize, Serialize},
     std::ops::{Add, Sub},
@@ -86,6 +87,44 @@ impl fmt::Display for SolanaAmount {
     }
 }
 
+/// Converts a UI amount such as "1.5" into base units of a token with
+/// `decimals`, e.g. 1_500_000 for 6 decimals. Fails rather than rounds when
+/// `ui` has more fractional digits than `decimals`.
+pub fn ui_amount_to_base(ui: &str, decimals: u8) -> Result<u64, TransactionError> {
+    let invalid = || SolanaError::Message(format!("Invalid amount '{}'", ui));
+    let (whole, fraction) = ui.split_once('.').unwrap_or((ui, ""));
+    let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if whole.is_empty() || !is_digits(whole) || !is_digits(fraction) {
+        return Err(invalid().into());
+    }
+    if ui.ends_with('.') {
+        return Err(invalid().into());
+    }
+    if fraction.len() > decimals as usize {
+        return Err(SolanaError::Message(format!(
+            "Amount '{}' has more than {} decimals",
+            ui, decimals
+        ))
+        .into());
+    }
+    let digits = format!("{}{:0<width$}", whole, fraction, width = decimals as usize);
+    Ok(digits.parse::<u64>().map_err(|_| invalid())?)
+}
+
+/// Formats `amount` base units of a token with `decimals` as a UI amount,
+/// dropping trailing zeros, e.g. "1.5" for 1_500_000 with 6 decimals
+pub fn base_to_ui_amount(amount: u64, decimals: u8) -> String {
+    // Works on the digits, 10^decimals overflows any integer type past 38
+    let decimals = decimals as usize;
+    let digits = format!("{:0>width$}", amount, width = decimals + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - decimals);
+    let fraction = fraction.trim_end_matches('0');
+    match fraction.is_empty() {
+        true => whole.to_string(),
+        false => format!("{}.{}", whole, fraction),
+    }
+}
+
 #[cfg(test)]
 #[allow(dead_code)]
 mod tests {
@@ -131,6 +170,33 @@ mod tests {
             .for_each(|amounts| test_from_sol(amounts.sol, amounts.lamports));
     }
 
+    #[test]
+    fn test_ui_amount() {
+        assert_eq!(ui_amount_to_base("1.5", 6).unwrap(), 1_500_000);
+        assert_eq!(base_to_ui_amount(1_500_000, 6), "1.5");
+        assert_eq!(ui_amount_to_base("0.000001", 6).unwrap(), 1);
+        assert_eq!(base_to_ui_amount(1, 6), "0.000001");
+        assert_eq!(ui_amount_to_base("42", 0).unwrap(), 42);
+        assert_eq!(base_to_ui_amount(42, 0), "42");
+        assert_eq!(base_to_ui_amount(u64::MAX, 19), "1.8446744073709551615");
+        assert_eq!(base_to_ui_amount(0, 6), "0");
+        assert_eq!(
+            base_to_ui_amount(15, 255),
+            format!("0.{}15", "0".repeat(253))
+        );
+        assert_eq!(base_to_ui_amount(0, 255), "0");
+        assert_eq!(
+            ui_amount_to_base("1.8446744073709551615", 19).unwrap(),
+            u64::MAX
+        );
+
+        for ui in ["", ".5", "1.", "-1", "1,5", "1.2.3", " 1", "1e6"] {
+            assert!(ui_amount_to_base(ui, 6).is_err(), "{}", ui);
+        }
+        assert!(ui_amount_to_base("0.0000001", 6).is_err());
+        assert!(ui_amount_to_base("18446744073709.551616", 6).is_err());
+    }
+
     fn test_addition(a: &str, b: &str, result: &str) {
         let a = SolanaAmount::from_lamports(a).unwrap();
         let b = SolanaAmount::from_lamports(b).unwrap();
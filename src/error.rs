@@ -0,0 +1,99 @@
+//! `SolanaError`, the errors of this crate. The `try_*` parsers of
+//! `SolanaTransaction` return it, so callers can match on the cause. It
+//! converts to and from the `TransactionError` of the `anychain_core` traits,
+//! a `TransactionError` being kept as is in the `Transaction` variant.
+
+use anychain_core::TransactionError;
+use solana_sdk::{program_error::ProgramError, pubkey::ParsePubkeyError};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SolanaError {
+    /// `bs58` is built without `std`, so its error is not a `source`
+    #[error("invalid base58: {0}")]
+    Base58(bs58::decode::Error),
+
+    #[error("invalid base64: {0}")]
+    Base64(#[from] base64::DecodeError),
+
+    #[error("invalid encoding: {0}")]
+    Bincode(#[from] bincode::Error),
+
+    #[error("invalid public key: {0}")]
+    Pubkey(#[from] ParsePubkeyError),
+
+    #[error("program error: {0}")]
+    Program(#[from] ProgramError),
+
+    #[error("{0}")]
+    Message(String),
+
+    #[error(transparent)]
+    Transaction(#[from] TransactionError),
+}
+
+impl From<bs58::decode::Error> for SolanaError {
+    fn from(error: bs58::decode::Error) -> Self {
+        SolanaError::Base58(error)
+    }
+}
+
+impl From<SolanaError> for TransactionError {
+    fn from(error: SolanaError) -> Self {
+        match error {
+            SolanaError::Transaction(error) => error,
+            error => TransactionError::Message(error.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SolanaTransaction;
+    use anychain_core::Transaction;
+    use std::{error::Error as _, str::FromStr};
+
+    #[test]
+    fn test_display() {
+        let error = SolanaTransaction::from_str("0OIl").unwrap_err();
+        assert!(error.to_string().contains("invalid base58"));
+        assert!(error.to_string().contains("'0'"));
+
+        let error = SolanaTransaction::from_bytes(&[1, 2, 3]).unwrap_err();
+        assert!(error.to_string().starts_with("invalid encoding"));
+
+        let error = SolanaError::from(bincode::deserialize::<u64>(&[]).unwrap_err());
+        assert!(error.source().is_some());
+    }
+
+    #[test]
+    fn test_typed_errors() {
+        assert!(matches!(
+            SolanaTransaction::try_from_base58("0OIl"),
+            Err(SolanaError::Base58(_))
+        ));
+        assert!(matches!(
+            SolanaTransaction::try_from_base64("!"),
+            Err(SolanaError::Base64(_))
+        ));
+        assert!(matches!(
+            SolanaTransaction::try_from_bytes(&[1, 2, 3]),
+            Err(SolanaError::Bincode(_))
+        ));
+
+        // A transaction without any instruction decodes but isn't recognized
+        let message = solana_sdk::message::Message::new(&[], None);
+        let bytes =
+            bincode::serialize(&solana_sdk::transaction::Transaction::new_unsigned(message))
+                .unwrap();
+        let error = SolanaTransaction::try_from_bytes(&bytes).unwrap_err();
+        assert!(matches!(error, SolanaError::Transaction(_)));
+        assert_eq!(
+            TransactionError::from(error).to_string(),
+            SolanaTransaction::from_bytes(&bytes)
+                .unwrap_err()
+                .to_string()
+        );
+    }
+}
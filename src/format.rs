@@ -1,19 +1,78 @@
 use {
+    crate::{SPL_TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID},
     anychain_core::Format,
     core::{default::Default, fmt},
+    solana_sdk::pubkey::Pubkey,
 };
 
+/// A token program that token accounts can belong to
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SolanaTokenProgram {
+    /// The SPL token program
+    #[default]
+    Spl,
+    /// The SPL Token-2022 program
+    Token2022,
+}
+
+impl SolanaTokenProgram {
+    /// Returns the program id
+    pub fn id(&self) -> Pubkey {
+        match self {
+            SolanaTokenProgram::Spl => SPL_TOKEN_PROGRAM_ID,
+            SolanaTokenProgram::Token2022 => TOKEN_2022_PROGRAM_ID,
+        }
+    }
+}
+
+impl fmt::Display for SolanaTokenProgram {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolanaTokenProgram::Spl => write!(f, "Spl"),
+            SolanaTokenProgram::Token2022 => write!(f, "Token2022"),
+        }
+    }
+}
+
+/// Selects the token program that token accounts are derived for. Wallet
+/// addresses are the same in `Standard` and `Token2022`, `AssociatedToken`
+/// turns them into the wallet's token account for `mint`.
 #[derive(Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum SolanaFormat {
+    /// The SPL token program
     #[default]
     Standard,
+    /// The SPL Token-2022 program
+    Token2022,
+    /// The associated token account for `mint` of `program`
+    AssociatedToken {
+        mint: String,
+        program: SolanaTokenProgram,
+    },
+}
+
+impl SolanaFormat {
+    /// Returns the id of the token program of this format
+    pub fn token_program_id(&self) -> Pubkey {
+        match self {
+            SolanaFormat::Standard => SolanaTokenProgram::Spl.id(),
+            SolanaFormat::Token2022 => SolanaTokenProgram::Token2022.id(),
+            SolanaFormat::AssociatedToken { program, .. } => program.id(),
+        }
+    }
 }
 
 impl Format for SolanaFormat {}
 
 impl fmt::Display for SolanaFormat {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Standard")
+        match self {
+            SolanaFormat::Standard => write!(f, "Standard"),
+            SolanaFormat::Token2022 => write!(f, "Token2022"),
+            SolanaFormat::AssociatedToken { mint, program } => {
+                write!(f, "AssociatedToken({}, {})", mint, program)
+            }
+        }
     }
 }
 
@@ -24,5 +83,31 @@ mod tests {
     #[test]
     fn test_display() {
         assert_eq!(SolanaFormat::Standard.to_string(), "Standard");
+        assert_eq!(SolanaFormat::Token2022.to_string(), "Token2022");
+        let format = SolanaFormat::AssociatedToken {
+            mint: "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v".to_string(),
+            program: SolanaTokenProgram::Token2022,
+        };
+        assert_eq!(
+            format.to_string(),
+            "AssociatedToken(EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v, Token2022)"
+        );
+    }
+
+    #[test]
+    fn test_token_program_id() {
+        assert_eq!(
+            SolanaFormat::Standard.token_program_id(),
+            SPL_TOKEN_PROGRAM_ID
+        );
+        assert_eq!(
+            SolanaFormat::Token2022.token_program_id(),
+            TOKEN_2022_PROGRAM_ID
+        );
+        let format = SolanaFormat::AssociatedToken {
+            mint: "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v".to_string(),
+            program: SolanaTokenProgram::Token2022,
+        };
+        assert_eq!(format.token_program_id(), TOKEN_2022_PROGRAM_ID);
     }
 }
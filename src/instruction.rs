@@ -0,0 +1,501 @@
+//! Builders for SPL token setup instructions that sit outside a plain transfer,
+//! and `SolanaInstruction`, a typed view of the instructions of a transaction.
+
+use {
+    crate::{
+        address::SolanaAddress, transaction::SolanaRawInstruction, ASSOCIATED_TOKEN_PROGRAM_ID,
+        COMPUTE_BUDGET_PROGRAM_ID, MEMO_PROGRAM_ID, SPL_TOKEN_PROGRAM_ID, SYSTEM_PROGRAM_ID,
+        TOKEN_2022_PROGRAM_ID,
+    },
+    anychain_core::TransactionError,
+    serde::{Deserialize, Serialize},
+    solana_sdk::{
+        compute_budget::ComputeBudgetInstruction,
+        instruction::{AccountMeta, CompiledInstruction, Instruction},
+        program_pack::Pack,
+        pubkey::Pubkey,
+        system_instruction::{advance_nonce_account, create_account, transfer, SystemInstruction},
+    },
+    spl_associated_token_account::instruction::{
+        create_associated_token_account, create_associated_token_account_idempotent,
+    },
+    spl_token::{
+        id,
+        instruction::{
+            initialize_account as token_initialize_account,
+            initialize_mint as token_initialize_mint, TokenInstruction,
+        },
+        state::{Account, Mint},
+    },
+    spl_token_2022::instruction::{sync_native, transfer_checked},
+    std::str::FromStr,
+};
+
+pub(crate) fn account_key(keys: &[Pubkey], index: u8) -> Result<Pubkey, TransactionError> {
+    keys.get(index as usize).copied().ok_or_else(|| {
+        TransactionError::Message(format!("Account index {} is out of range", index))
+    })
+}
+
+pub(crate) fn program_id(
+    keys: &[Pubkey],
+    ix: &CompiledInstruction,
+) -> Result<Pubkey, TransactionError> {
+    account_key(keys, ix.program_id_index)
+}
+
+pub(crate) fn instruction_account(
+    keys: &[Pubkey],
+    ix: &CompiledInstruction,
+    position: usize,
+) -> Result<Pubkey, TransactionError> {
+    match ix.accounts.get(position) {
+        Some(index) => account_key(keys, *index),
+        None => Err(TransactionError::Message(format!(
+            "Instruction account {} is missing",
+            position
+        ))),
+    }
+}
+
+/// An account of an instruction with its signer and writable flags
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SolanaAccountMeta {
+    pub address: SolanaAddress,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+/// A fully-formed instruction of any program, the serializable counterpart
+/// of `solana_sdk::instruction::Instruction`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SolanaCustomInstruction {
+    pub program: SolanaAddress,
+    pub accounts: Vec<SolanaAccountMeta>,
+    pub data: Vec<u8>,
+}
+
+impl SolanaCustomInstruction {
+    /// Builds the instruction, keeping the flags of every account
+    pub fn to_instruction(&self) -> Result<Instruction, TransactionError> {
+        let accounts = self
+            .accounts
+            .iter()
+            .map(|meta| {
+                Ok(AccountMeta {
+                    pubkey: to_pubkey(&meta.address)?,
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                })
+            })
+            .collect::<Result<_, TransactionError>>()?;
+        Ok(Instruction::new_with_bytes(
+            to_pubkey(&self.program)?,
+            &self.data,
+            accounts,
+        ))
+    }
+}
+
+impl From<Instruction> for SolanaCustomInstruction {
+    fn from(ix: Instruction) -> Self {
+        SolanaCustomInstruction {
+            program: SolanaAddress(ix.program_id.to_string()),
+            accounts: ix
+                .accounts
+                .into_iter()
+                .map(|meta| SolanaAccountMeta {
+                    address: SolanaAddress(meta.pubkey.to_string()),
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                })
+                .collect(),
+            data: ix.data,
+        }
+    }
+}
+
+/// The shape of an instruction, for logging without decoding it
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SolanaInstructionSummary {
+    pub program: SolanaAddress,
+    pub account_count: usize,
+    pub data_len: usize,
+}
+
+/// A single instruction of a transaction. The commonly used ones are
+/// decoded into their own variant, everything else is kept as `Other`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SolanaInstruction {
+    SolTransfer {
+        from: SolanaAddress,
+        to: SolanaAddress,
+        lamports: u64,
+    },
+    /// `TransferChecked` between the token accounts `source` and
+    /// `destination` of `token_program`
+    TokenTransfer {
+        source: SolanaAddress,
+        mint: SolanaAddress,
+        destination: SolanaAddress,
+        authority: SolanaAddress,
+        amount: u64,
+        decimals: u8,
+        token_program: SolanaAddress,
+    },
+    /// Updates the amount of the wrapped SOL token account `account` to its
+    /// lamport balance
+    SyncNative {
+        account: SolanaAddress,
+        token_program: SolanaAddress,
+    },
+    /// Creates the associated token account of `wallet` for `mint` of
+    /// `token_program`, the idempotent variant succeeds if it already exists
+    CreateAta {
+        funder: SolanaAddress,
+        wallet: SolanaAddress,
+        mint: SolanaAddress,
+        idempotent: bool,
+        token_program: SolanaAddress,
+    },
+    AdvanceNonce {
+        nonce_account: SolanaAddress,
+        authority: SolanaAddress,
+    },
+    ComputeUnitLimit {
+        units: u32,
+    },
+    /// Priority fee in micro-lamports per compute unit
+    ComputeUnitPrice {
+        micro_lamports: u64,
+    },
+    Memo {
+        memo: String,
+    },
+    Other(SolanaRawInstruction),
+}
+
+impl SolanaInstruction {
+    /// Decodes `ix` of a message whose account keys are `keys`
+    pub fn decode(keys: &[Pubkey], ix: &CompiledInstruction) -> Result<Self, TransactionError> {
+        let account = |position: usize| {
+            instruction_account(keys, ix, position).map(|key| SolanaAddress(key.to_string()))
+        };
+        let program = program_id(keys, ix)?;
+
+        let decoded = if program == SYSTEM_PROGRAM_ID {
+            match bincode::deserialize::<SystemInstruction>(&ix.data) {
+                Ok(SystemInstruction::Transfer { lamports }) => {
+                    Some(SolanaInstruction::SolTransfer {
+                        from: account(0)?,
+                        to: account(1)?,
+                        lamports,
+                    })
+                }
+                Ok(SystemInstruction::AdvanceNonceAccount) => {
+                    Some(SolanaInstruction::AdvanceNonce {
+                        nonce_account: account(0)?,
+                        authority: account(2)?,
+                    })
+                }
+                _ => None,
+            }
+        } else if program == SPL_TOKEN_PROGRAM_ID || program == TOKEN_2022_PROGRAM_ID {
+            // Both programs share the encoding of the instructions decoded here
+            let token_program = SolanaAddress(program.to_string());
+            match TokenInstruction::unpack(&ix.data) {
+                Ok(TokenInstruction::TransferChecked { amount, decimals }) => {
+                    Some(SolanaInstruction::TokenTransfer {
+                        source: account(0)?,
+                        mint: account(1)?,
+                        destination: account(2)?,
+                        authority: account(3)?,
+                        amount,
+                        decimals,
+                        token_program,
+                    })
+                }
+                Ok(TokenInstruction::SyncNative) => Some(SolanaInstruction::SyncNative {
+                    account: account(0)?,
+                    token_program,
+                }),
+                _ => None,
+            }
+        } else if program == ASSOCIATED_TOKEN_PROGRAM_ID {
+            let idempotent = match ix.data.as_slice() {
+                [] | [0] => Some(false),
+                [1] => Some(true),
+                _ => None,
+            };
+            match idempotent {
+                Some(idempotent) => Some(SolanaInstruction::CreateAta {
+                    funder: account(0)?,
+                    wallet: account(2)?,
+                    mint: account(3)?,
+                    idempotent,
+                    token_program: account(5)?,
+                }),
+                None => None,
+            }
+        } else if program == COMPUTE_BUDGET_PROGRAM_ID {
+            // borsh encoding: a one byte variant index then the little endian value
+            match ix.data.split_first() {
+                Some((2, units)) => {
+                    units
+                        .try_into()
+                        .ok()
+                        .map(|units| SolanaInstruction::ComputeUnitLimit {
+                            units: u32::from_le_bytes(units),
+                        })
+                }
+                Some((3, price)) => {
+                    price
+                        .try_into()
+                        .ok()
+                        .map(|price| SolanaInstruction::ComputeUnitPrice {
+                            micro_lamports: u64::from_le_bytes(price),
+                        })
+                }
+                _ => None,
+            }
+        } else if program == MEMO_PROGRAM_ID {
+            String::from_utf8(ix.data.clone())
+                .ok()
+                .map(|memo| SolanaInstruction::Memo { memo })
+        } else {
+            None
+        };
+
+        match decoded {
+            Some(decoded) => Ok(decoded),
+            None => {
+                let accounts = (0..ix.accounts.len())
+                    .map(account)
+                    .collect::<Result<_, _>>()?;
+                Ok(SolanaInstruction::Other((
+                    SolanaAddress(program.to_string()),
+                    accounts,
+                    ix.data.clone(),
+                )))
+            }
+        }
+    }
+
+    /// Builds the instruction. `Other` instructions lose the signer and
+    /// writable flags of their accounts, they are rebuilt as writable
+    /// non-signers.
+    pub fn to_instruction(&self) -> Result<Instruction, TransactionError> {
+        let ix = match self {
+            SolanaInstruction::SolTransfer { from, to, lamports } => {
+                transfer(&to_pubkey(from)?, &to_pubkey(to)?, *lamports)
+            }
+            SolanaInstruction::TokenTransfer {
+                source,
+                mint,
+                destination,
+                authority,
+                amount,
+                decimals,
+                token_program,
+            } => transfer_checked(
+                &to_pubkey(token_program)?,
+                &to_pubkey(source)?,
+                &to_pubkey(mint)?,
+                &to_pubkey(destination)?,
+                &to_pubkey(authority)?,
+                &[],
+                *amount,
+                *decimals,
+            )
+            .map_err(|e| TransactionError::Message(format!("{}", e)))?,
+            SolanaInstruction::SyncNative {
+                account,
+                token_program,
+            } => sync_native(&to_pubkey(token_program)?, &to_pubkey(account)?)
+                .map_err(|e| TransactionError::Message(format!("{}", e)))?,
+            SolanaInstruction::CreateAta {
+                funder,
+                wallet,
+                mint,
+                idempotent,
+                token_program,
+            } => {
+                let create = match idempotent {
+                    true => create_associated_token_account_idempotent,
+                    false => create_associated_token_account,
+                };
+                create(
+                    &to_pubkey(funder)?,
+                    &to_pubkey(wallet)?,
+                    &to_pubkey(mint)?,
+                    &to_pubkey(token_program)?,
+                )
+            }
+            SolanaInstruction::AdvanceNonce {
+                nonce_account,
+                authority,
+            } => advance_nonce_account(&to_pubkey(nonce_account)?, &to_pubkey(authority)?),
+            SolanaInstruction::ComputeUnitLimit { units } => {
+                ComputeBudgetInstruction::set_compute_unit_limit(*units)
+            }
+            SolanaInstruction::ComputeUnitPrice { micro_lamports } => {
+                ComputeBudgetInstruction::set_compute_unit_price(*micro_lamports)
+            }
+            SolanaInstruction::Memo { memo } => {
+                Instruction::new_with_bytes(MEMO_PROGRAM_ID, memo.as_bytes(), vec![])
+            }
+            SolanaInstruction::Other((program, accounts, data)) => {
+                let accounts = accounts
+                    .iter()
+                    .map(|account| Ok(AccountMeta::new(to_pubkey(account)?, false)))
+                    .collect::<Result<_, TransactionError>>()?;
+                Instruction::new_with_bytes(to_pubkey(program)?, data, accounts)
+            }
+        };
+        Ok(ix)
+    }
+}
+
+pub(crate) fn to_pubkey(address: &SolanaAddress) -> Result<Pubkey, TransactionError> {
+    Pubkey::from_str(&address.0)
+        .map_err(|e| TransactionError::Message(format!("Invalid address {}: {}", address, e)))
+}
+
+/// Returns an `InitializeMint` instruction for `mint`.
+///
+/// The mint account must already exist with `Mint::LEN` bytes owned by the
+/// token program, so this instruction has to follow a
+/// `system_instruction::create_account` for the mint in the same transaction.
+pub fn initialize_mint(
+    mint: &SolanaAddress,
+    mint_authority: &SolanaAddress,
+    freeze_authority: Option<&SolanaAddress>,
+    decimals: u8,
+) -> Result<Instruction, TransactionError> {
+    let freeze_authority = freeze_authority.map(to_pubkey).transpose()?;
+    token_initialize_mint(
+        &id(),
+        &to_pubkey(mint)?,
+        &to_pubkey(mint_authority)?,
+        freeze_authority.as_ref(),
+        decimals,
+    )
+    .map_err(|e| TransactionError::Message(format!("{}", e)))
+}
+
+/// Returns an `InitializeAccount` instruction creating a token account of
+/// `mint` held by `owner`.
+///
+/// Like `initialize_mint`, it must follow a `system_instruction::create_account`
+/// allocating `Account::LEN` bytes owned by the token program.
+pub fn initialize_account(
+    account: &SolanaAddress,
+    mint: &SolanaAddress,
+    owner: &SolanaAddress,
+) -> Result<Instruction, TransactionError> {
+    token_initialize_account(
+        &id(),
+        &to_pubkey(account)?,
+        &to_pubkey(mint)?,
+        &to_pubkey(owner)?,
+    )
+    .map_err(|e| TransactionError::Message(format!("{}", e)))
+}
+
+/// Returns the `create_account` + `InitializeMint` pair, in that order.
+/// Both `payer` and `mint` have to sign the resulting transaction.
+pub fn create_mint(
+    payer: &SolanaAddress,
+    mint: &SolanaAddress,
+    rent_lamports: u64,
+    mint_authority: &SolanaAddress,
+    freeze_authority: Option<&SolanaAddress>,
+    decimals: u8,
+) -> Result<Vec<Instruction>, TransactionError> {
+    let ix_create = create_account(
+        &to_pubkey(payer)?,
+        &to_pubkey(mint)?,
+        rent_lamports,
+        Mint::LEN as u64,
+        &id(),
+    );
+    let ix_initialize = initialize_mint(mint, mint_authority, freeze_authority, decimals)?;
+    Ok(vec![ix_create, ix_initialize])
+}
+
+/// Returns the `create_account` + `InitializeAccount` pair, in that order.
+/// Both `payer` and `account` have to sign the resulting transaction.
+pub fn create_token_account(
+    payer: &SolanaAddress,
+    account: &SolanaAddress,
+    rent_lamports: u64,
+    mint: &SolanaAddress,
+    owner: &SolanaAddress,
+) -> Result<Vec<Instruction>, TransactionError> {
+    let ix_create = create_account(
+        &to_pubkey(payer)?,
+        &to_pubkey(account)?,
+        rent_lamports,
+        Account::LEN as u64,
+        &id(),
+    );
+    let ix_initialize = initialize_account(account, mint, owner)?;
+    Ok(vec![ix_create, ix_initialize])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{program_option::COption, system_program};
+    use spl_token::instruction::TokenInstruction;
+
+    const ALICE: &str = "8tR45MbTcEq1W4dMXnwe7KW7xqykNxnyoBQoASMtqHK";
+    const BOB: &str = "DPCG5xKuxK3NCL8FTn8h2vFharp9MbYmUZeSA2eLx4RG";
+    const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+    #[test]
+    fn test_initialize_mint() {
+        let payer = SolanaAddress(ALICE.to_string());
+        let mint = SolanaAddress(USDC.to_string());
+        let freeze_authority = SolanaAddress(BOB.to_string());
+
+        let ixs =
+            create_mint(&payer, &mint, 1_461_600, &payer, Some(&freeze_authority), 6).unwrap();
+        assert_eq!(ixs.len(), 2);
+        assert_eq!(ixs[0].program_id, system_program::id());
+        assert_eq!(ixs[1].program_id, id());
+
+        match TokenInstruction::unpack(&ixs[1].data).unwrap() {
+            TokenInstruction::InitializeMint {
+                decimals,
+                mint_authority,
+                freeze_authority,
+            } => {
+                assert_eq!(decimals, 6);
+                assert_eq!(mint_authority.to_string(), ALICE);
+                assert_eq!(
+                    freeze_authority,
+                    COption::Some(Pubkey::from_str(BOB).unwrap())
+                );
+            }
+            ix => panic!("unexpected instruction {:?}", ix),
+        }
+    }
+
+    #[test]
+    fn test_initialize_account() {
+        let payer = SolanaAddress(ALICE.to_string());
+        let account = SolanaAddress(BOB.to_string());
+        let mint = SolanaAddress(USDC.to_string());
+
+        let ixs = create_token_account(&payer, &account, 2_039_280, &mint, &payer).unwrap();
+        assert_eq!(ixs.len(), 2);
+        assert_eq!(ixs[1].accounts[0].pubkey.to_string(), BOB);
+        assert_eq!(ixs[1].accounts[1].pubkey.to_string(), USDC);
+        assert_eq!(ixs[1].accounts[2].pubkey.to_string(), ALICE);
+        assert_eq!(
+            TokenInstruction::unpack(&ixs[1].data).unwrap(),
+            TokenInstruction::InitializeAccount
+        );
+    }
+}
@@ -1,11 +1,25 @@
 pub mod address;
 pub mod amount;
+pub mod error;
 pub mod format;
+pub mod instruction;
+pub mod mnemonic;
+pub mod programs;
 pub mod public_key;
+#[cfg(feature = "rpc")]
+pub mod rpc;
+pub mod secret_key;
 pub mod transaction;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
 pub use self::address::*;
 pub use self::amount::*;
+pub use self::error::*;
 pub use self::format::*;
+pub use self::instruction::*;
+pub use self::mnemonic::*;
+pub use self::programs::*;
 pub use self::public_key::*;
+pub use self::secret_key::*;
 pub use self::transaction::*;
@@ -0,0 +1,165 @@
+//! Secret keys derived from BIP39 mnemonics the way Solana wallets do.
+
+use {
+    crate::{SolanaAddress, SolanaFormat, SolanaSecretKey},
+    anychain_core::AddressError,
+    bip39::{Language, Mnemonic, Seed},
+    ed25519_dalek::{ExpandedSecretKey, SecretKey},
+    solana_sdk::{
+        derivation_path::DerivationPath,
+        signer::keypair::{keypair_from_seed, keypair_from_seed_and_derivation_path},
+    },
+    zeroize::Zeroize,
+};
+
+/// The derivation path of the first account in Phantom, Solflare and
+/// `solana-keygen`
+pub const SOLANA_DERIVATION_PATH: &str = "m/44'/501'/0'/0'";
+
+/// Returns the secret key of an English `phrase` protected by `passphrase`.
+///
+/// With a `derivation_path` such as `SOLANA_DERIVATION_PATH` the key is
+/// derived with SLIP-0010. Without one the first 32 bytes of the BIP39 seed
+/// are the ed25519 secret, as `solana-keygen` does without `--derivation-path`.
+pub fn secret_key_from_mnemonic(
+    phrase: &str,
+    passphrase: &str,
+    derivation_path: Option<&str>,
+) -> Result<SolanaSecretKey, AddressError> {
+    let mnemonic = Mnemonic::from_phrase(phrase, Language::English)
+        .map_err(|error| AddressError::Crate("bip39", format!("{:?}", error)))?;
+    let seed = Seed::new(&mnemonic, passphrase);
+
+    let keypair = match derivation_path {
+        Some(path) => {
+            let path = DerivationPath::from_absolute_path_str(path)
+                .map_err(|error| AddressError::Crate("derivation path", format!("{:?}", error)))?;
+            keypair_from_seed_and_derivation_path(seed.as_bytes(), Some(path))
+        }
+        None => keypair_from_seed(seed.as_bytes()),
+    }
+    .map_err(|error| AddressError::Crate("ed25519", format!("{:?}", error)))?;
+
+    Ok(expand(keypair.secret()))
+}
+
+/// Returns the secret key at `m/44'/501'/<account>'/<change>'`, the path
+/// Phantom and Solflare enumerate accounts under
+pub fn secret_key_from_mnemonic_index(
+    phrase: &str,
+    passphrase: &str,
+    account: u32,
+    change: u32,
+) -> Result<SolanaSecretKey, AddressError> {
+    let path = format!("m/44'/501'/{}'/{}'", account, change);
+    secret_key_from_mnemonic(phrase, passphrase, Some(&path))
+}
+
+/// Returns the address at `m/44'/501'/<account>'/<change>'`, for scanning
+/// a seed for funded accounts
+pub fn address_from_mnemonic_index(
+    phrase: &str,
+    passphrase: &str,
+    account: u32,
+    change: u32,
+) -> Result<SolanaAddress, AddressError> {
+    secret_key_from_mnemonic_index(phrase, passphrase, account, change)?
+        .address(&SolanaFormat::default())
+}
+
+/// The crate's secret keys are the scalars of expanded ed25519 keys, which
+/// ed25519 clamps, reduced modulo the group order. Both give the same public
+/// key.
+pub(crate) fn expand(secret: &SecretKey) -> SolanaSecretKey {
+    let mut expanded = ExpandedSecretKey::from(secret).to_bytes();
+    let mut scalar = [0u8; 32];
+    scalar.copy_from_slice(&expanded[..32]);
+    expanded.zeroize();
+    SolanaSecretKey::from_bytes_mod_order(&mut scalar)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::Signer;
+
+    const PHRASE: &str =
+        "tide label income foot rather novel erupt cattle dignity tag robot intact";
+
+    /// Testnet USDC, the mint used by `examples/create-account.rs`
+    const EXAMPLE_MINT: &str = "Gh9ZwEmdLJ8DscKNTkTqPbNwLNNBjuSzaG9Vp2KGtKJr";
+
+    /// (mnemonic, address, associated token address) as asserted by
+    /// `examples/create-account.rs`
+    const EXAMPLE_PAIRS: [(&str, &str, &str); 1] = [(
+        PHRASE,
+        "FrnopYkANcjm98sHme5pAUcnfTGQBnJi3ZbLK2khFwjK",
+        "DoRuQrvyG6uPhwsNHtgTFHSjrhw7RbP9Lqi4VU4Ypz4q",
+    )];
+
+    #[test]
+    fn test_secret_key_from_mnemonic() {
+        for (phrase, expected_address, expected_token_address) in EXAMPLE_PAIRS {
+            let secret_key = secret_key_from_mnemonic(phrase, "", None).unwrap();
+            let address = secret_key.address(&SolanaFormat::default()).unwrap();
+            assert_eq!(address.to_string(), expected_address);
+
+            // the same key as the example's `generate_keypair_from_mnemonic`
+            let mnemonic = Mnemonic::from_phrase(phrase, Language::English).unwrap();
+            let seed = Seed::new(&mnemonic, "");
+            let keypair = keypair_from_seed(seed.as_bytes()).unwrap();
+            assert_eq!(keypair.pubkey().to_string(), expected_address);
+
+            let token_address = address
+                .associated_token_address(EXAMPLE_MINT.to_string())
+                .unwrap();
+            assert_eq!(token_address, expected_token_address);
+        }
+    }
+
+    #[test]
+    fn test_derivation_path() {
+        let secret_key =
+            secret_key_from_mnemonic(PHRASE, "", Some(SOLANA_DERIVATION_PATH)).unwrap();
+        let address = secret_key.address(&SolanaFormat::default()).unwrap();
+
+        let mnemonic = Mnemonic::from_phrase(PHRASE, Language::English).unwrap();
+        let seed = Seed::new(&mnemonic, "");
+        let path = DerivationPath::new_bip44(Some(0), Some(0));
+        let keypair = keypair_from_seed_and_derivation_path(seed.as_bytes(), Some(path)).unwrap();
+        assert_eq!(address.to_string(), keypair.pubkey().to_string());
+        assert_ne!(
+            address.to_string(),
+            "FrnopYkANcjm98sHme5pAUcnfTGQBnJi3ZbLK2khFwjK"
+        );
+
+        assert_eq!(
+            address,
+            address_from_mnemonic_index(PHRASE, "", 0, 0).unwrap()
+        );
+
+        assert!(secret_key_from_mnemonic(PHRASE, "", Some("m/44'/501'/x'")).is_err());
+        assert!(secret_key_from_mnemonic("tide label income", "", None).is_err());
+    }
+
+    #[test]
+    fn test_account_index() {
+        let expected = [
+            "Fti3ate6cWtA8T3WY9YFtV7uYvDgyPjvMBCbVnDwZ6ud",
+            "8D5jL81zvR71375LRZc85iEN7tvbj1etbSafVHWdHe7b",
+        ];
+        let mnemonic = Mnemonic::from_phrase(PHRASE, Language::English).unwrap();
+        let seed = Seed::new(&mnemonic, "");
+        for (account, expected) in expected.iter().enumerate() {
+            let account = account as u32;
+            let address = address_from_mnemonic_index(PHRASE, "", account, 0).unwrap();
+            assert_eq!(&address.to_string(), expected);
+
+            // same derivation as solana-keygen
+            let path = DerivationPath::new_bip44(Some(account), Some(0));
+            let keypair =
+                keypair_from_seed_and_derivation_path(seed.as_bytes(), Some(path)).unwrap();
+            assert_eq!(address.to_string(), keypair.pubkey().to_string());
+        }
+    }
+}
@@ -0,0 +1,67 @@
+//! Ids of the on-chain programs whose instructions this crate builds and parses.
+
+use solana_sdk::{compute_budget, pubkey::Pubkey, stake, system_program};
+
+/// The system program, owner of plain SOL accounts and durable nonces
+pub const SYSTEM_PROGRAM_ID: Pubkey = system_program::ID;
+
+/// The SPL token program
+pub const SPL_TOKEN_PROGRAM_ID: Pubkey = spl_token::ID;
+
+/// The SPL Token-2022 program, the token program with extensions
+pub const TOKEN_2022_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+/// The SPL associated token account program
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey = spl_associated_token_account::ID;
+
+/// The compute budget program, setting the compute unit limit and price
+pub const COMPUTE_BUDGET_PROGRAM_ID: Pubkey = compute_budget::ID;
+
+/// The stake program, delegating SOL to validators
+pub const STAKE_PROGRAM_ID: Pubkey = stake::program::ID;
+
+/// The SPL memo program (v2)
+pub const MEMO_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_program_ids() {
+        assert_eq!(SYSTEM_PROGRAM_ID, system_program::id());
+        assert_eq!(SPL_TOKEN_PROGRAM_ID, spl_token::id());
+        assert_eq!(
+            ASSOCIATED_TOKEN_PROGRAM_ID,
+            spl_associated_token_account::id()
+        );
+        assert_eq!(COMPUTE_BUDGET_PROGRAM_ID, compute_budget::id());
+        assert_eq!(
+            SYSTEM_PROGRAM_ID,
+            Pubkey::from_str("11111111111111111111111111111111").unwrap()
+        );
+        assert_eq!(
+            SPL_TOKEN_PROGRAM_ID,
+            Pubkey::from_str("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA").unwrap()
+        );
+        assert_eq!(
+            ASSOCIATED_TOKEN_PROGRAM_ID,
+            Pubkey::from_str("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL").unwrap()
+        );
+        assert_eq!(
+            TOKEN_2022_PROGRAM_ID.to_string(),
+            "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb"
+        );
+        assert_eq!(
+            STAKE_PROGRAM_ID.to_string(),
+            "Stake11111111111111111111111111111111111111"
+        );
+        assert_eq!(
+            MEMO_PROGRAM_ID.to_string(),
+            "MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr"
+        );
+    }
+}
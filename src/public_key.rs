@@ -5,22 +5,37 @@ use {
     curve25519_dalek::{constants::ED25519_BASEPOINT_TABLE as G, Scalar},
     ed25519_dalek::PUBLIC_KEY_LENGTH,
     group::GroupEncoding,
+    solana_sdk::pubkey::Pubkey,
+    subtle::ConstantTimeEq,
 };
 
 /// Maximum string length of a base58 encoded pubkey
 pub const MAX_BASE58_LEN: usize = 44;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// Minimum string length of a base58 encoded pubkey (32 zero bytes encode to 32 '1's)
+pub const MIN_BASE58_LEN: usize = 32;
+
+#[derive(Debug, Clone)]
 pub struct SolanaPublicKey(pub ed25519_dalek::PublicKey);
 
+/// Compares the encoded keys in constant time, as signer checks may compare
+/// against a key an attacker probes for
+impl PartialEq for SolanaPublicKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_bytes().ct_eq(other.0.as_bytes()).into()
+    }
+}
+
+impl Eq for SolanaPublicKey {}
+
 impl PublicKey for SolanaPublicKey {
     type SecretKey = Scalar;
     type Address = SolanaAddress;
     type Format = SolanaFormat;
 
+    /// Prefer `SolanaSecretKey::public_key`, which zeroizes the secret
     fn from_secret_key(secret_key: &Self::SecretKey) -> Self {
-        let public_key = secret_key * G;
-        let public_key = public_key.to_bytes();
+        let public_key = Self::bytes_from_secret_key(secret_key);
         let public_key = ed25519_dalek::PublicKey::from_bytes(&public_key).unwrap();
         SolanaPublicKey(public_key)
     }
@@ -30,13 +45,39 @@ impl PublicKey for SolanaPublicKey {
     }
 }
 
+impl SolanaPublicKey {
+    /// Shadows `PublicKey::from_secret_key` to deprecate the raw scalar,
+    /// generic code over the trait is unaffected
+    #[deprecated(note = "use `SolanaSecretKey::public_key`, which zeroizes the secret")]
+    pub fn from_secret_key(secret_key: &Scalar) -> Self {
+        <Self as PublicKey>::from_secret_key(secret_key)
+    }
+
+    /// Returns the encoded public key of `secret_key` without building an
+    /// `ed25519_dalek::PublicKey`, which decompresses the point again. Meant
+    /// for hot loops such as vanity address grinding.
+    pub fn bytes_from_secret_key(secret_key: &Scalar) -> [u8; PUBLIC_KEY_LENGTH] {
+        (secret_key * G).to_bytes()
+    }
+
+    /// Returns the address of this public key, Solana having a single
+    /// address format
+    pub fn address(&self) -> Result<SolanaAddress, AddressError> {
+        self.to_address(&SolanaFormat::default())
+    }
+}
+
 impl FromStr for SolanaPublicKey {
     type Err = PublicKeyError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Too long keeps its historical error, too short has its own
         if s.len() > MAX_BASE58_LEN {
             return Err(PublicKeyError::InvalidByteLength(s.len()));
         }
+        if s.len() < MIN_BASE58_LEN {
+            return Err(PublicKeyError::InvalidCharacterLength(s.len()));
+        }
         let pubkey_vec = bs58::decode(s)
             .into_vec()
             .map_err(|error| PublicKeyError::Crate("base58", format!("{:?}", error)))?;
@@ -50,6 +91,42 @@ impl FromStr for SolanaPublicKey {
     }
 }
 
+impl TryFrom<&[u8]> for SolanaPublicKey {
+    type Error = PublicKeyError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        if bytes.len() != PUBLIC_KEY_LENGTH {
+            return Err(PublicKeyError::InvalidByteLength(bytes.len()));
+        }
+        let public_key = ed25519_dalek::PublicKey::from_bytes(bytes)
+            .map_err(|error| PublicKeyError::Crate("ed25519", format!("{:?}", error)))?;
+        Ok(SolanaPublicKey(public_key))
+    }
+}
+
+impl TryFrom<[u8; PUBLIC_KEY_LENGTH]> for SolanaPublicKey {
+    type Error = PublicKeyError;
+
+    fn try_from(bytes: [u8; PUBLIC_KEY_LENGTH]) -> Result<Self, Self::Error> {
+        Self::try_from(bytes.as_slice())
+    }
+}
+
+impl From<&SolanaPublicKey> for Pubkey {
+    fn from(public_key: &SolanaPublicKey) -> Self {
+        Pubkey::new_from_array(public_key.0.to_bytes())
+    }
+}
+
+/// Fallible because a `Pubkey` may be off the ed25519 curve (e.g. a PDA)
+impl TryFrom<&Pubkey> for SolanaPublicKey {
+    type Error = PublicKeyError;
+
+    fn try_from(pubkey: &Pubkey) -> Result<Self, Self::Error> {
+        Self::try_from(pubkey.to_bytes())
+    }
+}
+
 impl fmt::Display for SolanaPublicKey {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", bs58::encode(self.0.to_bytes()).into_string())
@@ -59,6 +136,7 @@ impl fmt::Display for SolanaPublicKey {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::SolanaSecretKey;
 
     #[test]
     fn test_public_key_from_str() {
@@ -68,4 +146,80 @@ mod tests {
         let pubkey = pubkey_res.unwrap();
         assert_eq!(pubkey.to_string(), pubkey_str);
     }
+
+    #[test]
+    fn test_public_key_address() {
+        let pubkey_str = "EpFLfuH524fk9QP9i9uL9AHtX6smBaxaMHwek9T11nK5";
+        let pubkey = SolanaPublicKey::from_str(pubkey_str).unwrap();
+        assert_eq!(
+            pubkey.address().unwrap(),
+            pubkey.to_address(&SolanaFormat::default()).unwrap()
+        );
+        assert_eq!(pubkey.address().unwrap().to_string(), pubkey_str);
+    }
+
+    #[test]
+    fn test_public_key_eq() {
+        let alice =
+            SolanaPublicKey::from_str("EpFLfuH524fk9QP9i9uL9AHtX6smBaxaMHwek9T11nK5").unwrap();
+        let bob =
+            SolanaPublicKey::from_str("DPCG5xKuxK3NCL8FTn8h2vFharp9MbYmUZeSA2eLx4RG").unwrap();
+        assert_eq!(alice, alice.clone());
+        assert_ne!(alice, bob);
+    }
+
+    #[test]
+    fn test_bytes_from_secret_key() {
+        let secret_key = Scalar::from_bytes_mod_order([7u8; 32]);
+        let public_key = SolanaSecretKey::from(secret_key).public_key();
+        assert_eq!(
+            SolanaPublicKey::bytes_from_secret_key(&secret_key),
+            public_key.0.to_bytes()
+        );
+    }
+
+    #[test]
+    fn test_public_key_try_from_bytes() {
+        let pubkey_str = "EpFLfuH524fk9QP9i9uL9AHtX6smBaxaMHwek9T11nK5";
+        let bytes: [u8; PUBLIC_KEY_LENGTH] = bs58::decode(pubkey_str)
+            .into_vec()
+            .unwrap()
+            .try_into()
+            .unwrap();
+
+        let pubkey = SolanaPublicKey::try_from(bytes).unwrap();
+        assert_eq!(pubkey.to_string(), pubkey_str);
+        let pubkey = SolanaPublicKey::try_from(&bytes[..]).unwrap();
+        assert_eq!(pubkey.to_string(), pubkey_str);
+
+        assert!(matches!(
+            SolanaPublicKey::try_from(&bytes[1..]),
+            Err(PublicKeyError::InvalidByteLength(31))
+        ));
+    }
+
+    #[test]
+    fn test_public_key_to_pubkey() {
+        let pubkey_str = "EpFLfuH524fk9QP9i9uL9AHtX6smBaxaMHwek9T11nK5";
+        let public_key = SolanaPublicKey::from_str(pubkey_str).unwrap();
+
+        let pubkey = Pubkey::from(&public_key);
+        assert_eq!(pubkey.to_string(), public_key.to_string());
+        assert_eq!(SolanaPublicKey::try_from(&pubkey).unwrap(), public_key);
+    }
+
+    #[test]
+    fn test_public_key_from_str_invalid_length() {
+        for s in ["", "E"] {
+            assert!(matches!(
+                SolanaPublicKey::from_str(s),
+                Err(PublicKeyError::InvalidCharacterLength(len)) if len == s.len()
+            ));
+        }
+        let s = "EpFLfuH524fk9QP9i9uL9AHtX6smBaxaMHwek9T11nK5E";
+        assert!(matches!(
+            SolanaPublicKey::from_str(s),
+            Err(PublicKeyError::InvalidByteLength(45))
+        ));
+    }
 }
@@ -0,0 +1,236 @@
+//! Helpers that talk to a Solana RPC node, enabled by the `rpc` feature.
+
+use {
+    crate::{
+        transaction::{SolanaTransaction, SolanaTransactionId},
+        SolanaAddress, SPL_TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID,
+    },
+    anychain_core::Transaction,
+    anyhow::anyhow,
+    solana_rpc_client::rpc_client::RpcClient,
+    solana_sdk::{
+        account::Account,
+        commitment_config::CommitmentConfig,
+        hash::Hash,
+        nonce::state::{State, Versions},
+        program_pack::Pack,
+        pubkey::Pubkey,
+        transaction::VersionedTransaction,
+    },
+    spl_associated_token_account::{
+        get_associated_token_address_with_program_id, instruction::create_associated_token_account,
+    },
+    spl_token::state::Mint,
+    std::str::FromStr,
+};
+
+/// Fetches the latest blockhash from the node at `rpc_url`, base58 encoded
+pub fn fetch_latest_blockhash(rpc_url: &str) -> anyhow::Result<String> {
+    let client = RpcClient::new(rpc_url.to_string());
+    Ok(client.get_latest_blockhash()?.to_string())
+}
+
+/// Returns true if `address` holds an account, i.e. it has been funded
+pub fn account_exists(client: &RpcClient, address: &SolanaAddress) -> anyhow::Result<bool> {
+    let pubkey = Pubkey::from_str(&address.0)?;
+    let account = client.get_account_with_commitment(&pubkey, CommitmentConfig::confirmed())?;
+    Ok(account.value.is_some())
+}
+
+/// Returns an unsigned transaction, paid by `payer`, creating the associated
+/// token account of `owner` for `mint`, or `None` if it already exists. The
+/// account belongs to the token program that owns `mint`.
+pub fn ensure_token_account(
+    client: &RpcClient,
+    payer: &SolanaAddress,
+    owner: &SolanaAddress,
+    mint: &SolanaAddress,
+) -> anyhow::Result<Option<SolanaTransaction>> {
+    let token_program = fetch_mint_account(client, mint)?.owner;
+    let owner = Pubkey::from_str(&owner.0)?;
+    let mint = Pubkey::from_str(&mint.0)?;
+    let account = get_associated_token_address_with_program_id(&owner, &mint, &token_program);
+    if account_exists(client, &SolanaAddress(account.to_string()))? {
+        return Ok(None);
+    }
+    let ix = create_associated_token_account(
+        &Pubkey::from_str(&payer.0)?,
+        &owner,
+        &mint,
+        &token_program,
+    );
+    let blockhash = client.get_latest_blockhash()?;
+    Ok(Some(SolanaTransaction::from_instructions(
+        vec![ix],
+        payer.clone(),
+        blockhash.to_string(),
+    )?))
+}
+
+/// Reads the decimals of the token `mint` from its on-chain account, an
+/// SPL Token or Token-2022 mint
+pub fn fetch_mint_decimals(client: &RpcClient, mint: &SolanaAddress) -> anyhow::Result<u8> {
+    let account = fetch_mint_account(client, mint)?;
+    // Token-2022 mints append their extensions after the base layout
+    let data = account
+        .data
+        .get(..Mint::LEN)
+        .ok_or_else(|| anyhow!("{} is not a mint account", mint))?;
+    Ok(Mint::unpack(data)?.decimals)
+}
+
+/// Fetches the account of `mint`, which a token program has to own
+fn fetch_mint_account(client: &RpcClient, mint: &SolanaAddress) -> anyhow::Result<Account> {
+    let pubkey = Pubkey::from_str(&mint.0)?;
+    let account = client.get_account(&pubkey)?;
+    if account.owner != SPL_TOKEN_PROGRAM_ID && account.owner != TOKEN_2022_PROGRAM_ID {
+        return Err(anyhow!("{} is not owned by a token program", mint));
+    }
+    Ok(account)
+}
+
+/// Sends the signed `tx` and waits for its confirmation, unlike
+/// `SolanaTransaction::submit` which returns once the node accepted it
+pub fn broadcast(
+    client: &RpcClient,
+    tx: &SolanaTransaction,
+) -> anyhow::Result<SolanaTransactionId> {
+    if !tx.is_fully_signed() {
+        return Err(anyhow!("Transaction is missing signatures"));
+    }
+    let versioned = bincode::deserialize::<VersionedTransaction>(&tx.to_bytes()?)?;
+    client.send_and_confirm_transaction(&versioned)?;
+    Ok(tx.to_transaction_id()?)
+}
+
+impl SolanaTransaction {
+    /// Sends the signed transaction to the node at `rpc_url` and returns
+    /// its transaction id
+    pub fn submit(&self, rpc_url: &str) -> anyhow::Result<SolanaTransactionId> {
+        if !self.is_fully_signed() {
+            return Err(anyhow!("Transaction is missing signatures"));
+        }
+        let tx = bincode::deserialize::<VersionedTransaction>(&self.to_bytes()?)?;
+        let client = RpcClient::new(rpc_url.to_string());
+        client.send_transaction(&tx)?;
+        Ok(self.to_transaction_id()?)
+    }
+
+    /// Checks that the blockhash of a durable-nonce transaction equals the
+    /// nonce currently stored on chain. A mismatch means the nonce has been
+    /// advanced and the transaction is stale.
+    pub fn verify_nonce(&self, client: &RpcClient) -> anyhow::Result<bool> {
+        let nonce_account = self
+            .params
+            .nonce_account
+            .as_ref()
+            .ok_or_else(|| anyhow!("Transaction does not use a durable nonce"))?;
+        let nonce_account = Pubkey::from_str(&nonce_account.0)?;
+        let blockhash = Hash::from_str(&self.params.blockhash)?;
+
+        let account = client.get_account(&nonce_account)?;
+        let versions = bincode::deserialize::<Versions>(&account.data)?;
+        match versions.state() {
+            State::Initialized(data) => Ok(data.blockhash() == blockhash),
+            State::Uninitialized => {
+                Err(anyhow!("Nonce account {} is uninitialized", nonce_account))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SolanaAddress, SolanaTransactionParameters};
+    use solana_sdk::signature::{Keypair, Signer};
+
+    const DEVNET: &str = "https://api.devnet.solana.com";
+
+    #[test]
+    #[ignore]
+    fn test_account_checks() {
+        let client = RpcClient::new(DEVNET.to_string());
+        assert!(account_exists(&client, &SolanaAddress::token_program()).unwrap());
+        let unused = SolanaAddress(Keypair::new().pubkey().to_string());
+        assert!(!account_exists(&client, &unused).unwrap());
+
+        let payer = SolanaAddress("DPCG5xKuxK3NCL8FTn8h2vFharp9MbYmUZeSA2eLx4RG".to_string());
+        let usdc = SolanaAddress("4zMMC9srt5Ri5X14GAgXhaHii3GnPAEERYPJgZJDncDU".to_string());
+        let tx = ensure_token_account(&client, &payer, &unused, &usdc)
+            .unwrap()
+            .unwrap();
+        assert_eq!(tx.required_signers().unwrap(), vec![payer.clone()]);
+        assert!(broadcast(&client, &tx).is_err());
+        assert!(tx.submit(DEVNET).is_err());
+        assert!(
+            ensure_token_account(&client, &payer, &unused, &SolanaAddress::token_program())
+                .is_err()
+        );
+    }
+
+    #[test]
+    #[ignore]
+    fn test_fetch_mint_decimals() {
+        let client = RpcClient::new(DEVNET.to_string());
+        let usdc = SolanaAddress("4zMMC9srt5Ri5X14GAgXhaHii3GnPAEERYPJgZJDncDU".to_string());
+        assert_eq!(fetch_mint_decimals(&client, &usdc).unwrap(), 6);
+        assert!(fetch_mint_decimals(&client, &SolanaAddress::token_program()).is_err());
+    }
+
+    #[test]
+    #[ignore]
+    fn test_submit() {
+        let keypair = Keypair::new();
+        let client = RpcClient::new(DEVNET.to_string());
+        let sig = client
+            .request_airdrop(&keypair.pubkey(), 10_000_000)
+            .unwrap();
+        while !client.confirm_transaction(&sig).unwrap() {
+            std::thread::sleep(std::time::Duration::from_millis(500));
+        }
+
+        let params = SolanaTransactionParameters::native(
+            SolanaAddress(keypair.pubkey().to_string()),
+            SolanaAddress("DPCG5xKuxK3NCL8FTn8h2vFharp9MbYmUZeSA2eLx4RG".to_string()),
+            1_000_000,
+            fetch_latest_blockhash(DEVNET).unwrap(),
+        );
+        let mut tx = SolanaTransaction::new(&params).unwrap();
+        assert!(tx.submit(DEVNET).is_err());
+
+        let msg = tx.to_bytes().unwrap();
+        tx.sign(keypair.sign_message(&msg).as_ref().to_vec(), 0)
+            .unwrap();
+        let txid = tx.submit(DEVNET).unwrap();
+        assert_eq!(txid, tx.to_transaction_id().unwrap());
+    }
+
+    #[test]
+    #[ignore]
+    fn test_verify_nonce() {
+        let client = RpcClient::new(DEVNET.to_string());
+        let nonce_account = "5ZbuQ6BBj5rAmqv6MgN4dHgSr5rn4Y3D8ybqDcAyyq73";
+        let pubkey = Pubkey::from_str(nonce_account).unwrap();
+        let account = client.get_account(&pubkey).unwrap();
+        let versions = bincode::deserialize::<Versions>(&account.data).unwrap();
+        let (authority, nonce) = match versions.state() {
+            State::Initialized(data) => (data.authority, data.blockhash()),
+            State::Uninitialized => panic!("nonce account is uninitialized"),
+        };
+
+        let params = SolanaTransactionParameters {
+            from: SolanaAddress(authority.to_string()),
+            to: SolanaAddress("DPCG5xKuxK3NCL8FTn8h2vFharp9MbYmUZeSA2eLx4RG".to_string()),
+            amount: 1000,
+            blockhash: nonce.to_string(),
+            nonce_account: Some(SolanaAddress(nonce_account.to_string())),
+            ..Default::default()
+        };
+        let mut tx = SolanaTransaction::new(&params).unwrap();
+        assert!(tx.verify_nonce(&client).unwrap());
+
+        tx.params.blockhash = Hash::default().to_string();
+        assert!(!tx.verify_nonce(&client).unwrap());
+    }
+}
@@ -0,0 +1,83 @@
+//! `SolanaSecretKey`, a secret scalar wiped from memory when dropped.
+
+use {
+    crate::{SolanaAddress, SolanaFormat, SolanaPublicKey},
+    anychain_core::{AddressError, PublicKey},
+    core::fmt,
+    curve25519_dalek::Scalar,
+    zeroize::{Zeroize, ZeroizeOnDrop},
+};
+
+/// A secret scalar reduced modulo the group order, the secret key type of the
+/// `anychain_core` traits, zeroized on drop. Prefer it to a bare `Scalar`,
+/// whose copies linger in memory.
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct SolanaSecretKey(Scalar);
+
+impl SolanaSecretKey {
+    /// Reduces `bytes` modulo the group order, then zeroizes the caller's
+    /// `bytes`
+    pub fn from_bytes_mod_order(bytes: &mut [u8; 32]) -> Self {
+        let secret_key = SolanaSecretKey(Scalar::from_bytes_mod_order(*bytes));
+        bytes.zeroize();
+        secret_key
+    }
+
+    /// Returns the scalar for APIs that take it directly
+    pub fn as_scalar(&self) -> &Scalar {
+        &self.0
+    }
+
+    pub fn public_key(&self) -> SolanaPublicKey {
+        <SolanaPublicKey as PublicKey>::from_secret_key(&self.0)
+    }
+
+    pub fn address(&self, format: &SolanaFormat) -> Result<SolanaAddress, AddressError> {
+        self.public_key().to_address(format)
+    }
+}
+
+impl From<Scalar> for SolanaSecretKey {
+    fn from(scalar: Scalar) -> Self {
+        SolanaSecretKey(scalar)
+    }
+}
+
+/// Never prints the secret
+impl fmt::Debug for SolanaSecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SolanaSecretKey(..)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_secret_key() {
+        let bytes = [
+            41, 196, 252, 146, 80, 100, 13, 46, 69, 89, 172, 157, 224, 135, 23, 62, 54, 65, 52, 68,
+            14, 50, 112, 112, 156, 210, 24, 236, 139, 169, 38, 63,
+        ];
+        let scalar = Scalar::from_bytes_mod_order(bytes);
+        let mut wiped = bytes;
+        let secret_key = SolanaSecretKey::from_bytes_mod_order(&mut wiped);
+        assert_eq!(secret_key.as_scalar(), &scalar);
+        assert_eq!(wiped, [0u8; 32]);
+        assert_eq!(
+            secret_key.address(&SolanaFormat::default()).unwrap(),
+            SolanaAddress::from_secret_key(&scalar, &SolanaFormat::default()).unwrap()
+        );
+        assert_eq!(
+            SolanaSecretKey::from(scalar).public_key(),
+            secret_key.public_key()
+        );
+        assert_eq!(format!("{:?}", secret_key), "SolanaSecretKey(..)");
+
+        let mut secret_key = secret_key;
+        secret_key.zeroize();
+        assert_eq!(secret_key.as_scalar(), &Scalar::ZERO);
+    }
+}
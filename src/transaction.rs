@@ -1,49 +1,877 @@
-use crate::{SolanaAddress, SolanaFormat, SolanaPublicKey};
+use crate::{
+    instruction::{account_key, instruction_account, program_id, to_pubkey},
+    ui_amount_to_base, SolanaAddress, SolanaCustomInstruction, SolanaError, SolanaFormat,
+    SolanaInstruction, SolanaInstructionSummary, SolanaPublicKey, ASSOCIATED_TOKEN_PROGRAM_ID,
+    COMPUTE_BUDGET_PROGRAM_ID, MEMO_PROGRAM_ID, SPL_TOKEN_PROGRAM_ID, STAKE_PROGRAM_ID,
+    SYSTEM_PROGRAM_ID, TOKEN_2022_PROGRAM_ID,
+};
 use anychain_core::{Transaction, TransactionError, TransactionId};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
 use solana_sdk::{
-    hash::Hash,
-    message::Message,
+    address_lookup_table::AddressLookupTableAccount,
+    compute_budget::ComputeBudgetInstruction,
+    hash::{hash, Hash},
+    instruction::{CompiledInstruction, Instruction},
+    message::{
+        v0::{self, MessageAddressTableLookup},
+        Message, VersionedMessage,
+    },
+    packet::PACKET_DATA_SIZE,
+    program_error::ProgramError,
     pubkey::Pubkey,
+    rent::Rent,
     signature::Signature,
-    system_instruction::{transfer as sol_transfer, SystemInstruction},
-    transaction::Transaction as Tx,
+    stake::instruction::{deactivate_stake, delegate_stake, StakeInstruction},
+    system_instruction::{
+        advance_nonce_account, allocate, assign, create_account, create_account_with_seed,
+        create_nonce_account, transfer as sol_transfer, transfer_with_seed, SystemInstruction,
+    },
+    transaction::{Transaction as Tx, VersionedTransaction},
 };
 use spl_associated_token_account::{
-    get_associated_token_address, instruction::create_associated_token_account,
+    get_associated_token_address, get_associated_token_address_with_program_id,
+    instruction::{create_associated_token_account, create_associated_token_account_idempotent},
 };
 use spl_token::{
     id,
-    instruction::{transfer_checked as token_transfer, TokenInstruction},
+    instruction::{AuthorityType, TokenInstruction},
+    native_mint,
+};
+// The Token-2022 builders accept either token program, unlike those of
+// `spl_token`
+use spl_token_2022::{
+    extension::transfer_fee::instruction::{transfer_checked_with_fee, TransferFeeInstruction},
+    instruction::{
+        approve_checked, freeze_account, mint_to_checked, revoke, set_authority, sync_native,
+        thaw_account, transfer_checked as token_transfer, AuthorityType as Token2022AuthorityType,
+    },
 };
-use std::{fmt, str::FromStr};
+use std::{collections::HashMap, fmt, str::FromStr};
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// Optional fields may be left out of the JSON, only `from`, `to`, `amount`
+/// and `blockhash` are required
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct SolanaTransactionParameters {
+    #[serde(default)]
     pub token: Option<SolanaAddress>,
+    /// Whether the recipient's associated token account exists.
+    /// `Some(false)` creates it before the transfer, `None` creates it
+    /// idempotently, which does nothing if it already exists.
+    #[serde(default)]
     pub has_token_account: Option<bool>,
+    #[serde(default)]
     pub decimals: Option<u8>,
+    /// Token program of `token` and of the token operations, the SPL token
+    /// program if unset. Mints of the Token-2022 program need it set to that
+    /// program.
+    #[serde(default)]
+    pub token_program: Option<SolanaAddress>,
+    /// Fee withheld by a Token-2022 mint with the transfer fee extension.
+    /// If set, the transfer is a `TransferCheckedWithFee`, which fails
+    /// unless the fee matches the one the mint charges on `amount`.
+    #[serde(default)]
+    pub transfer_fee: Option<u64>,
     pub from: SolanaAddress,
     pub to: SolanaAddress,
     pub amount: u64,
     pub blockhash: String,
+    /// Last block height at which `blockhash` is accepted, as returned
+    /// alongside it by `getLatestBlockhash`. It is not part of the message.
+    #[serde(default)]
+    pub last_valid_block_height: Option<u64>,
+    /// Durable nonce account, if set `blockhash` holds the stored nonce value
+    #[serde(default)]
+    pub nonce_account: Option<SolanaAddress>,
+    /// Authority of the nonce account, defaults to `from`
+    #[serde(default)]
+    pub nonce_authority: Option<SolanaAddress>,
+    /// Operation to perform instead of a plain SOL or token transfer
+    #[serde(default)]
+    pub operation: Option<SolanaOperation>,
+    /// Account funding the creation of the recipient's associated token
+    /// account, defaults to `from`. A distinct funder must also sign.
+    #[serde(default)]
+    pub funder: Option<SolanaAddress>,
+    /// Address lookup tables to compile a v0 message against, `None` builds
+    /// a legacy message
+    #[serde(default)]
+    pub lookup_tables: Option<Vec<SolanaLookupTable>>,
+    /// Account paying the transaction fee, defaults to `from`. A distinct
+    /// fee payer must also sign.
+    #[serde(default)]
+    pub fee_payer: Option<SolanaAddress>,
+    /// Compute unit limit requested through the compute budget program
+    #[serde(default)]
+    pub compute_unit_limit: Option<u32>,
+    /// Priority fee in micro-lamports per compute unit
+    #[serde(default)]
+    pub compute_unit_price: Option<u64>,
+    /// Signers of a token transfer or operation whose authority `from` is an
+    /// SPL token multisig account. They sign instead of `from`, so
+    /// `fee_payer`, and `funder` when the recipient's token account is
+    /// created, have to be set as well.
+    #[serde(default)]
+    pub multisig_signers: Option<Vec<SolanaAddress>>,
+    /// Memo appended as the last instruction, with no signers
+    #[serde(default)]
+    pub memo: Option<String>,
+}
+
+impl SolanaTransactionParameters {
+    /// Returns `blockhash` together with its last valid block height
+    pub fn recent_blockhash(&self) -> SolanaBlockhash {
+        SolanaBlockhash {
+            hash: self.blockhash.clone(),
+            last_valid_block_height: self.last_valid_block_height,
+        }
+    }
+
+    /// Checks that the addresses and the blockhash parse, that a token
+    /// transfer says whether the recipient's token account exists and that
+    /// transfers move a non-zero amount
+    pub fn validate(&self) -> Result<(), TransactionError> {
+        self.validate_structure()?;
+        self.validate_amounts()
+    }
+
+    /// Checks everything `validate` does but the amounts, which may be zero
+    /// in a transaction parsed from the chain
+    fn validate_structure(&self) -> Result<(), TransactionError> {
+        to_pubkey(&self.from)?;
+        to_pubkey(&self.to)?;
+        if let Some(fee_payer) = &self.fee_payer {
+            to_pubkey(fee_payer)?;
+        }
+        for signer in self.multisig_signers.iter().flatten() {
+            to_pubkey(signer)?;
+        }
+        if let Some(token_program) = &self.token_program {
+            let token_program = to_pubkey(token_program)?;
+            if token_program != SPL_TOKEN_PROGRAM_ID && token_program != TOKEN_2022_PROGRAM_ID {
+                return Err(TransactionError::Message(format!(
+                    "{} is not a token program",
+                    token_program
+                )));
+            }
+        }
+        if self.transfer_fee.is_some()
+            && self.token_program != Some(SolanaAddress(TOKEN_2022_PROGRAM_ID.to_string()))
+        {
+            return Err(TransactionError::Message(
+                "Transfer fees require the Token-2022 program".to_string(),
+            ));
+        }
+        if self.multisig_signers.is_some() && !self.is_token_operation() {
+            return Err(TransactionError::Message(
+                "Multisig signers only apply to token operations".to_string(),
+            ));
+        }
+        if let Some(token) = &self.token {
+            to_pubkey(token)?;
+        }
+        Hash::from_str(&self.blockhash).map_err(|e| {
+            TransactionError::Message(format!("Invalid blockhash {}: {}", self.blockhash, e))
+        })?;
+        match &self.operation {
+            Some(SolanaOperation::MultiTransfer { recipients }) => {
+                total_amount(recipients).map(|_| ())
+            }
+            Some(SolanaOperation::CreateAccountWithSeed {
+                base, seed, owner, ..
+            }) => {
+                let derived = Pubkey::create_with_seed(&to_pubkey(base)?, seed, &to_pubkey(owner)?)
+                    .map_err(|e| TransactionError::Message(format!("{}", e)))?;
+                if derived.to_string() != self.to.0 {
+                    return Err(TransactionError::Message(format!(
+                        "{} is not derived from base {} and seed {}",
+                        self.to, base, seed
+                    )));
+                }
+                Ok(())
+            }
+            Some(SolanaOperation::Instructions { instructions }) if instructions.is_empty() => Err(
+                TransactionError::Message("No instructions are provided".to_string()),
+            ),
+            Some(SolanaOperation::TokenBatch { transfers }) => {
+                if transfers.is_empty() {
+                    return Err(TransactionError::Message(
+                        "Token batch has no transfers".to_string(),
+                    ));
+                }
+                for transfer in transfers {
+                    to_pubkey(&transfer.token)?;
+                    to_pubkey(&transfer.to)?;
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks that the operation moves a non-zero amount to each recipient
+    fn validate_amounts(&self) -> Result<(), TransactionError> {
+        let zero_to = |to: &SolanaAddress| {
+            Err(TransactionError::Message(format!(
+                "Amount to {} must be greater than zero",
+                to
+            )))
+        };
+        match &self.operation {
+            None
+            | Some(SolanaOperation::TransferWithSeed { .. })
+            | Some(SolanaOperation::MintTo)
+            | Some(SolanaOperation::CreateAccount { .. })
+            | Some(SolanaOperation::CreateNonceAccount { .. })
+            | Some(SolanaOperation::WrapSol)
+                if self.amount == 0 =>
+            {
+                Err(TransactionError::Message(
+                    "Amount must be greater than zero".to_string(),
+                ))
+            }
+            Some(SolanaOperation::MultiTransfer { recipients }) => {
+                match recipients.iter().find(|r| r.amount == 0) {
+                    Some(recipient) => zero_to(&recipient.to),
+                    None => Ok(()),
+                }
+            }
+            Some(SolanaOperation::TokenBatch { transfers }) => {
+                match transfers.iter().find(|t| t.amount == 0) {
+                    Some(transfer) => zero_to(&transfer.to),
+                    None => Ok(()),
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns true if `from` acts as the authority of a token account or
+    /// mint, which a multisig account can be
+    fn is_token_operation(&self) -> bool {
+        match &self.operation {
+            None | Some(SolanaOperation::MultiTransfer { .. }) => self.token.is_some(),
+            Some(SolanaOperation::Approve)
+            | Some(SolanaOperation::Revoke)
+            | Some(SolanaOperation::TokenBatch { .. })
+            | Some(SolanaOperation::MintTo)
+            | Some(SolanaOperation::FreezeAccount)
+            | Some(SolanaOperation::ThawAccount)
+            | Some(SolanaOperation::SetAuthority { .. })
+            | Some(SolanaOperation::UncheckedTokenTransfer { .. }) => true,
+            _ => false,
+        }
+    }
+
+    /// Creates and initializes the durable nonce account `nonce_account`
+    /// with `lamports` of rent, paid by `from`. Both `from` and
+    /// `nonce_account` have to sign.
+    pub fn create_nonce_account(
+        from: SolanaAddress,
+        nonce_account: SolanaAddress,
+        authority: SolanaAddress,
+        lamports: u64,
+        blockhash: String,
+    ) -> Self {
+        SolanaTransactionParameters {
+            from,
+            to: nonce_account,
+            amount: lamports,
+            blockhash,
+            operation: Some(SolanaOperation::CreateNonceAccount { authority }),
+            ..Default::default()
+        }
+    }
+
+    /// Creates the account `to`, rent-exempt for `space` bytes and owned by
+    /// `owner`, then transfers `amount` lamports to it. Both `from` and `to`
+    /// have to sign.
+    pub fn create_account(
+        from: SolanaAddress,
+        to: SolanaAddress,
+        amount: u64,
+        space: u64,
+        owner: SolanaAddress,
+        blockhash: String,
+    ) -> Self {
+        let lamports = Rent::default().minimum_balance(space as usize);
+        SolanaTransactionParameters {
+            from,
+            to,
+            amount,
+            blockhash,
+            operation: Some(SolanaOperation::CreateAccount {
+                lamports,
+                space,
+                owner,
+            }),
+            ..Default::default()
+        }
+    }
+
+    /// Creates the account derived from `base`, `seed` and `owner` with
+    /// `lamports` and `space` bytes, paid by `from`. `base` has to sign as
+    /// well when it differs from `from`.
+    pub fn create_account_with_seed(
+        from: SolanaAddress,
+        base: SolanaAddress,
+        seed: String,
+        lamports: u64,
+        space: u64,
+        owner: SolanaAddress,
+        blockhash: String,
+    ) -> Result<Self, TransactionError> {
+        let to = Pubkey::create_with_seed(&to_pubkey(&base)?, &seed, &to_pubkey(&owner)?)
+            .map_err(|e| TransactionError::Message(format!("{}", e)))?;
+        Ok(SolanaTransactionParameters {
+            from,
+            to: SolanaAddress(to.to_string()),
+            amount: lamports,
+            blockhash,
+            operation: Some(SolanaOperation::CreateAccountWithSeed {
+                base,
+                seed,
+                space,
+                owner,
+            }),
+            ..Default::default()
+        })
+    }
+
+    /// Wraps `amount` lamports into the wrapped SOL associated token account
+    /// of `from`, which has to exist already
+    pub fn wrap_sol(
+        from: SolanaAddress,
+        amount: u64,
+        blockhash: String,
+    ) -> Result<Self, TransactionError> {
+        let account = get_associated_token_address(&to_pubkey(&from)?, &native_mint::id());
+        Ok(SolanaTransactionParameters {
+            from,
+            to: SolanaAddress(account.to_string()),
+            amount,
+            blockhash,
+            operation: Some(SolanaOperation::WrapSol),
+            ..Default::default()
+        })
+    }
+
+    /// Mints `amount` of `mint` (with `decimals`) into the token `account`,
+    /// signed by the mint authority `authority`
+    pub fn mint_to(
+        mint: SolanaAddress,
+        account: SolanaAddress,
+        authority: SolanaAddress,
+        amount: u64,
+        decimals: u8,
+        blockhash: String,
+    ) -> Self {
+        SolanaTransactionParameters {
+            token: Some(mint),
+            decimals: Some(decimals),
+            from: authority,
+            to: account,
+            amount,
+            blockhash,
+            operation: Some(SolanaOperation::MintTo),
+            ..Default::default()
+        }
+    }
+
+    /// Delegates the stake account `stake` to the validator `vote_account`,
+    /// signed by the stake authority `authority`
+    pub fn delegate_stake(
+        stake: SolanaAddress,
+        vote_account: SolanaAddress,
+        authority: SolanaAddress,
+        blockhash: String,
+    ) -> Self {
+        SolanaTransactionParameters {
+            from: authority,
+            to: stake,
+            blockhash,
+            operation: Some(SolanaOperation::DelegateStake { vote_account }),
+            ..Default::default()
+        }
+    }
+
+    /// Deactivates the stake account `stake`, signed by the stake authority
+    /// `authority`. The stake can be withdrawn once it has cooled down.
+    pub fn deactivate_stake(
+        stake: SolanaAddress,
+        authority: SolanaAddress,
+        blockhash: String,
+    ) -> Self {
+        SolanaTransactionParameters {
+            from: authority,
+            to: stake,
+            blockhash,
+            operation: Some(SolanaOperation::DeactivateStake),
+            ..Default::default()
+        }
+    }
+
+    /// Transfers `amount` lamports from `from` to `to`
+    pub fn native(from: SolanaAddress, to: SolanaAddress, amount: u64, blockhash: String) -> Self {
+        SolanaTransactionParameters {
+            from,
+            to,
+            amount,
+            blockhash,
+            ..Default::default()
+        }
+    }
+
+    /// Transfers `amount` lamports to `to`, whose existence the caller
+    /// looked up as `has_account`. A missing recipient is created by the
+    /// transfer itself, so it has to carry at least `rent_exempt_minimum`,
+    /// the cluster's minimum balance of an account without data.
+    pub fn native_to_account(
+        from: SolanaAddress,
+        to: SolanaAddress,
+        amount: u64,
+        has_account: Option<bool>,
+        rent_exempt_minimum: u64,
+        blockhash: String,
+    ) -> Result<Self, TransactionError> {
+        match has_account {
+            None => Err(TransactionError::Message(format!(
+                "Whether the recipient {} exists is not provided",
+                to
+            ))),
+            Some(false) if amount < rent_exempt_minimum => Err(TransactionError::Message(format!(
+                "Amount {} is below the rent-exempt minimum {} of the new account {}",
+                amount, rent_exempt_minimum, to
+            ))),
+            _ => Ok(SolanaTransactionParameters::native(
+                from, to, amount, blockhash,
+            )),
+        }
+    }
+
+    /// Transfers the `balance` of `from` to `to`, less the fee of its one
+    /// signature and `rent_exempt_minimum`, which is left behind. Pass a
+    /// zero minimum to empty the account.
+    pub fn sweep(
+        from: SolanaAddress,
+        to: SolanaAddress,
+        balance: u64,
+        rent_exempt_minimum: u64,
+        blockhash: String,
+    ) -> Self {
+        let amount = max_transferable(balance, 1, rent_exempt_minimum);
+        SolanaTransactionParameters::native(from, to, amount, blockhash)
+    }
+
+    /// Transfers `amount` of `token` from the associated token account of
+    /// `from` to the one of `to`, creating the latter first unless
+    /// `has_token_account` is set
+    pub fn token(
+        token: SolanaAddress,
+        has_token_account: bool,
+        from: SolanaAddress,
+        to: SolanaAddress,
+        amount: u64,
+        decimals: u8,
+        blockhash: String,
+    ) -> Self {
+        SolanaTransactionParameters {
+            token: Some(token),
+            has_token_account: Some(has_token_account),
+            decimals: Some(decimals),
+            from,
+            to,
+            amount,
+            blockhash,
+            ..Default::default()
+        }
+    }
+
+    /// Like `token`, but takes the amount as a UI amount such as "1.5",
+    /// converted to base units with `decimals`
+    pub fn token_ui(
+        token: SolanaAddress,
+        has_token_account: bool,
+        from: SolanaAddress,
+        to: SolanaAddress,
+        ui_amount: &str,
+        decimals: u8,
+        blockhash: String,
+    ) -> Result<Self, TransactionError> {
+        let amount = ui_amount_to_base(ui_amount, decimals)?;
+        Ok(SolanaTransactionParameters::token(
+            token,
+            has_token_account,
+            from,
+            to,
+            amount,
+            decimals,
+            blockhash,
+        ))
+    }
+}
+
+/// Writes the params as JSON, meant for logs and operator tooling rather
+/// than the wire. `FromStr` parses it back.
+impl fmt::Display for SolanaTransactionParameters {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let json = serde_json::to_string(self).map_err(|_| fmt::Error)?;
+        write!(f, "{}", json)
+    }
+}
+
+impl FromStr for SolanaTransactionParameters {
+    type Err = TransactionError;
+    fn from_str(params: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(params).map_err(|e| TransactionError::Message(format!("{}", e)))
+    }
+}
+
+/// Base fee charged per signature, priority fees excluded
+pub const LAMPORTS_PER_SIGNATURE: u64 = 5000;
+
+/// Returns the lamports an account holding `balance` can send in a
+/// transaction carrying `num_signatures` signatures while keeping
+/// `rent_exempt_minimum`, or zero if the balance does not cover both
+pub fn max_transferable(balance: u64, num_signatures: u64, rent_exempt_minimum: u64) -> u64 {
+    let fee = num_signatures.saturating_mul(LAMPORTS_PER_SIGNATURE);
+    balance
+        .saturating_sub(fee)
+        .saturating_sub(rent_exempt_minimum)
+}
+
+/// A recent blockhash and, if known, the last block height at which it is
+/// accepted
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SolanaBlockhash {
+    pub hash: String,
+    pub last_valid_block_height: Option<u64>,
+}
+
+impl SolanaBlockhash {
+    /// Tells whether the blockhash has expired at `current_block_height`,
+    /// `None` if its last valid block height is unknown
+    pub fn is_expired(&self, current_block_height: u64) -> Option<bool> {
+        self.last_valid_block_height
+            .map(|height| current_block_height > height)
+    }
+}
+
+/// A recipient of a multi-recipient transfer
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SolanaRecipient {
+    pub to: SolanaAddress,
+    pub amount: u64,
+}
+
+/// Transfers of SOL, or of `token` with `decimals`, from `from` to many
+/// recipients. Token recipients must already hold an associated token
+/// account.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SolanaMultiTransferParameters {
+    pub token: Option<SolanaAddress>,
+    pub decimals: Option<u8>,
+    pub from: SolanaAddress,
+    pub recipients: Vec<SolanaRecipient>,
+}
+
+impl SolanaMultiTransferParameters {
+    /// Returns the sum of the amounts sent to all recipients
+    pub fn total_amount(&self) -> Result<u64, TransactionError> {
+        total_amount(&self.recipients)
+    }
+
+    /// Greedily packs the recipients, in order, into as few transactions as
+    /// possible while keeping each signed transaction within
+    /// `PACKET_DATA_SIZE` bytes. Every transaction is signed by `from` alone.
+    pub fn chunk_by_size(
+        &self,
+        blockhash: String,
+    ) -> Result<Vec<SolanaTransaction>, TransactionError> {
+        self.total_amount()?;
+
+        let mut txs = vec![];
+        let mut chunk: Vec<SolanaRecipient> = vec![];
+        for recipient in &self.recipients {
+            chunk.push(recipient.clone());
+            if self.transaction(chunk.clone(), &blockhash)?.size_bytes()? > PACKET_DATA_SIZE {
+                let recipient = chunk.pop().unwrap();
+                if chunk.is_empty() {
+                    return Err(TransactionError::Message(format!(
+                        "Transfer to {} exceeds the transaction size limit",
+                        recipient.to
+                    )));
+                }
+                txs.push(self.transaction(std::mem::take(&mut chunk), &blockhash)?);
+                chunk.push(recipient);
+            }
+        }
+        if !chunk.is_empty() {
+            txs.push(self.transaction(chunk, &blockhash)?);
+        }
+        Ok(txs)
+    }
+
+    fn transaction(
+        &self,
+        recipients: Vec<SolanaRecipient>,
+        blockhash: &str,
+    ) -> Result<SolanaTransaction, TransactionError> {
+        let params = SolanaTransactionParameters {
+            token: self.token.clone(),
+            decimals: self.decimals,
+            from: self.from.clone(),
+            to: self.from.clone(),
+            blockhash: blockhash.to_string(),
+            operation: Some(SolanaOperation::MultiTransfer { recipients }),
+            ..Default::default()
+        };
+        SolanaTransaction::new(&params)
+    }
+}
+
+/// A transfer of `amount` of `token`, with `decimals`, to the token account
+/// `to`. `SolanaAddress::associated_token_address` gives the associated
+/// token account of a wallet.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SolanaTokenTransfer {
+    pub token: SolanaAddress,
+    pub to: SolanaAddress,
+    pub amount: u64,
+    pub decimals: u8,
+}
+
+/// Transfers of several tokens, possibly of different mints, from the
+/// associated token accounts of `from` in a single transaction
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SolanaTokenBatchParameters {
+    pub from: SolanaAddress,
+    pub transfers: Vec<SolanaTokenTransfer>,
+}
+
+impl SolanaTokenBatchParameters {
+    /// Builds the transaction, one `TransferChecked` per transfer in order
+    pub fn transaction(&self, blockhash: String) -> Result<SolanaTransaction, TransactionError> {
+        let params = SolanaTransactionParameters {
+            from: self.from.clone(),
+            to: self.from.clone(),
+            blockhash,
+            operation: Some(SolanaOperation::TokenBatch {
+                transfers: self.transfers.clone(),
+            }),
+            ..Default::default()
+        };
+        SolanaTransaction::new(&params)
+    }
+}
+
+/// An address lookup table and the addresses it resolves
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SolanaLookupTable {
+    pub address: SolanaAddress,
+    pub addresses: Vec<SolanaAddress>,
+}
+
+/// Operations other than a plain transfer. `from` is always the signing
+/// authority and fee payer, each variant documents how it uses the other
+/// fields of `SolanaTransactionParameters`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SolanaOperation {
+    /// Lets the delegate `to` spend up to `amount` of `token` (with
+    /// `decimals`) from the associated token account of `from`
+    Approve,
+    /// Removes the delegate of the token account `to` owned by `from`,
+    /// `amount` is unused
+    Revoke,
+    /// Transfers `amount` lamports to `to` from the account derived from
+    /// the base `from`, `seed` and the program `owner`, see
+    /// `Pubkey::create_with_seed`. The instruction takes the derived source,
+    /// the signing base and the recipient, in that order.
+    TransferWithSeed { seed: String, owner: SolanaAddress },
+    /// Transfers SOL, or `token` with `decimals` between associated token
+    /// accounts, from `from` to each of `recipients`. `to` and `amount` are
+    /// unused.
+    MultiTransfer { recipients: Vec<SolanaRecipient> },
+    /// Transfers each of `transfers` from the associated token account of
+    /// `from` for its mint. `token`, `to`, `amount` and `decimals` are
+    /// unused.
+    TokenBatch { transfers: Vec<SolanaTokenTransfer> },
+    /// Wraps `amount` lamports of `from` into the wrapped SOL token account
+    /// `to`: a SOL transfer followed by `SyncNative`
+    WrapSol,
+    /// Mints `amount` of `token` (with `decimals`) into the token account
+    /// `to`, `from` being the mint authority
+    MintTo,
+    /// Creates the durable nonce account `to` funded with `amount` lamports
+    /// of rent and initializes it with `authority`
+    CreateNonceAccount { authority: SolanaAddress },
+    /// Emits `instructions` as they are, `from` being the fee payer. `to`
+    /// and `amount` are unused.
+    Instructions {
+        instructions: Vec<SolanaCustomInstruction>,
+    },
+    /// Allocates `space` bytes of data for the account `from`. `to` and
+    /// `amount` are unused.
+    Allocate { space: u64 },
+    /// Assigns the account `from` to the program `owner`. `to` and `amount`
+    /// are unused.
+    Assign { owner: SolanaAddress },
+    /// Creates the account `to`, derived from `base`, `seed` and `owner`,
+    /// with `amount` lamports and `space` bytes of data. `base` has to sign
+    /// as well when it differs from `from`.
+    CreateAccountWithSeed {
+        base: SolanaAddress,
+        seed: String,
+        space: u64,
+        owner: SolanaAddress,
+    },
+    /// Creates the account `to` with `lamports`, `space` bytes of data and
+    /// `owner` as its program, then transfers `amount` lamports to it. `to`
+    /// has to sign as well.
+    CreateAccount {
+        lamports: u64,
+        space: u64,
+        owner: SolanaAddress,
+    },
+    /// Changes the `authority_type` authority of the mint or token account
+    /// `to` from `from` to `new_authority`, `None` removes it for good
+    SetAuthority {
+        authority_type: SolanaAuthorityType,
+        new_authority: Option<SolanaAddress>,
+    },
+    /// Freezes the token account `to` of `token`, `from` being the freeze
+    /// authority. Only mints created with a freeze authority support it,
+    /// `amount` is unused.
+    FreezeAccount,
+    /// Thaws the frozen token account `to` of `token`, `from` being the
+    /// freeze authority. `amount` is unused.
+    ThawAccount,
+    /// Delegates the stake account `to` to the validator `vote_account`,
+    /// `from` being the stake authority. `amount` is unused.
+    DelegateStake { vote_account: SolanaAddress },
+    /// Deactivates the stake account `to`, `from` being the stake
+    /// authority. `amount` is unused.
+    DeactivateStake,
+    /// Transfers `amount` from the token account `source` to the token
+    /// account `to` with the deprecated `Transfer`, which names neither the
+    /// mint nor its decimals. `from` is the owner of `source`. `from_bytes`
+    /// yields it for such transfers, new ones should be checked transfers.
+    UncheckedTokenTransfer { source: SolanaAddress },
+}
+
+/// Mirrors `spl_token::instruction::AuthorityType`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SolanaAuthorityType {
+    MintTokens,
+    FreezeAccount,
+    AccountOwner,
+    CloseAccount,
+}
+
+impl From<SolanaAuthorityType> for AuthorityType {
+    fn from(authority_type: SolanaAuthorityType) -> Self {
+        match authority_type {
+            SolanaAuthorityType::MintTokens => AuthorityType::MintTokens,
+            SolanaAuthorityType::FreezeAccount => AuthorityType::FreezeAccount,
+            SolanaAuthorityType::AccountOwner => AuthorityType::AccountOwner,
+            SolanaAuthorityType::CloseAccount => AuthorityType::CloseAccount,
+        }
+    }
+}
+
+impl From<SolanaAuthorityType> for Token2022AuthorityType {
+    fn from(authority_type: SolanaAuthorityType) -> Self {
+        match authority_type {
+            SolanaAuthorityType::MintTokens => Token2022AuthorityType::MintTokens,
+            SolanaAuthorityType::FreezeAccount => Token2022AuthorityType::FreezeAccount,
+            SolanaAuthorityType::AccountOwner => Token2022AuthorityType::AccountOwner,
+            SolanaAuthorityType::CloseAccount => Token2022AuthorityType::CloseAccount,
+        }
+    }
+}
+
+impl From<AuthorityType> for SolanaAuthorityType {
+    fn from(authority_type: AuthorityType) -> Self {
+        match authority_type {
+            AuthorityType::MintTokens => SolanaAuthorityType::MintTokens,
+            AuthorityType::FreezeAccount => SolanaAuthorityType::FreezeAccount,
+            AuthorityType::AccountOwner => SolanaAuthorityType::AccountOwner,
+            AuthorityType::CloseAccount => SolanaAuthorityType::CloseAccount,
+        }
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// An instruction of a program this crate does not model, as
+/// `(program_id, accounts, data)`
+pub type SolanaRawInstruction = (SolanaAddress, Vec<SolanaAddress>, Vec<u8>);
+
+/// `==` compares the signatures too, use `same_params` to compare only what
+/// is being signed
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct SolanaTransaction {
     pub params: SolanaTransactionParameters,
-    pub signature: Option<Vec<u8>>,
+    /// Signatures in the order of the signers in the message header, the fee
+    /// payer's first. Serialized as base58 strings, the encoding explorers
+    /// and RPC use.
+    #[serde(with = "base58_signatures")]
+    pub signature: Vec<Vec<u8>>,
+    /// Unrecognized instructions found by `from_bytes`, kept for inspection.
+    /// The message built from the params would leave them out, so
+    /// `to_bytes`, and with it `sign`, fail while any is present.
+    pub unknown_instructions: Vec<SolanaRawInstruction>,
 }
 
 impl FromStr for SolanaTransaction {
     type Err = TransactionError;
     fn from_str(tx: &str) -> Result<Self, Self::Err> {
-        let tx = bs58::decode(tx)
-            .into_vec()
-            .map_err(|e| TransactionError::Message(format!("{}", e)))?;
-        SolanaTransaction::from_bytes(&tx)
+        Ok(SolanaTransaction::try_from_base58(tx)?)
+    }
+}
+
+/// Writes the base58 serialization, or nothing if the transaction can't be
+/// serialized. Use `to_base58` to see the error.
+impl fmt::Display for SolanaTransaction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.to_base58() {
+            Ok(tx) => write!(f, "{}", tx),
+            Err(_) => Ok(()),
+        }
+    }
+}
+
+/// (De)serializes signatures as a list of base58 strings
+mod base58_signatures {
+    use serde::{de::Error, ser::SerializeSeq, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        signatures: &[Vec<u8>],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(signatures.len()))?;
+        for signature in signatures {
+            seq.serialize_element(&bs58::encode(signature).into_string())?;
+        }
+        seq.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Vec<u8>>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .into_iter()
+            .map(|signature| {
+                bs58::decode(signature)
+                    .into_vec()
+                    .map_err(|e| D::Error::custom(format!("{:?}", e)))
+            })
+            .collect()
     }
 }
 
+/// The fee payer's ed25519 signature, which identifies the transaction. The
+/// bytes are the signature as serialized on the wire, R then S, with no
+/// byte order conversion.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct SolanaTransactionId(pub [u8; 64]);
 
@@ -55,42 +883,277 @@ impl fmt::Display for SolanaTransactionId {
 
 impl TransactionId for SolanaTransactionId {}
 
-impl Transaction for SolanaTransaction {
-    type Address = SolanaAddress;
-    type Format = SolanaFormat;
-    type PublicKey = SolanaPublicKey;
-    type TransactionParameters = SolanaTransactionParameters;
-    type TransactionId = SolanaTransactionId;
+impl From<[u8; 64]> for SolanaTransactionId {
+    fn from(signature: [u8; 64]) -> Self {
+        SolanaTransactionId(signature)
+    }
+}
 
-    fn new(params: &Self::TransactionParameters) -> Result<Self, TransactionError> {
-        Ok(SolanaTransaction {
-            params: params.clone(),
-            signature: None,
+impl AsRef<[u8]> for SolanaTransactionId {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// Parses the base58 signature that `Display` writes
+impl FromStr for SolanaTransactionId {
+    type Err = TransactionError;
+    fn from_str(txid: &str) -> Result<Self, Self::Err> {
+        let bytes = bs58::decode(txid)
+            .into_vec()
+            .map_err(|e| TransactionError::Message(format!("{}", e)))?;
+        let signature: [u8; 64] = bytes.try_into().map_err(|bytes: Vec<u8>| {
+            TransactionError::Message(format!("Invalid transaction id length {}", bytes.len()))
+        })?;
+        Ok(SolanaTransactionId(signature))
+    }
+}
+
+impl SolanaTransactionId {
+    /// Returns the signature bytes
+    pub fn bytes(&self) -> &[u8; 64] {
+        &self.0
+    }
+
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.0.to_vec()
+    }
+
+    /// Returns the Solana Explorer page of this transaction on `cluster`
+    pub fn explorer_url(&self, cluster: SolanaCluster) -> String {
+        format!(
+            "https://explorer.solana.com/tx/{}{}",
+            self,
+            cluster.explorer_query()
+        )
+    }
+}
+
+/// Solana clusters, the public ones or a custom one by its RPC URL
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SolanaCluster {
+    Mainnet,
+    Testnet,
+    Devnet,
+    Custom(String),
+}
+
+impl SolanaCluster {
+    /// Returns the default RPC endpoint of the cluster
+    pub fn rpc_url(&self) -> &str {
+        match self {
+            SolanaCluster::Mainnet => "https://api.mainnet-beta.solana.com",
+            SolanaCluster::Testnet => "https://api.testnet.solana.com",
+            SolanaCluster::Devnet => "https://api.devnet.solana.com",
+            SolanaCluster::Custom(url) => url,
+        }
+    }
+
+    /// Returns the query selecting the cluster on Solana Explorer, which
+    /// for a custom cluster carries its URL
+    pub fn explorer_query(&self) -> String {
+        match self {
+            SolanaCluster::Mainnet => String::new(),
+            SolanaCluster::Testnet => "?cluster=testnet".to_string(),
+            SolanaCluster::Devnet => "?cluster=devnet".to_string(),
+            SolanaCluster::Custom(url) => {
+                format!("?cluster=custom&customUrl={}", percent_encode(url))
+            }
+        }
+    }
+}
+
+/// Percent-encodes everything but the unreserved characters of RFC 3986
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
         })
+        .collect()
+}
+
+impl SolanaTransaction {
+    /// Builds a token transfer, asking `recipient_has_ata` whether the
+    /// associated token account it is given already exists instead of
+    /// reading `has_token_account` from `params`. The callback is the place
+    /// to look the account up, this crate doesn't do any I/O itself.
+    pub fn new_token_transfer_with<F: FnOnce(&SolanaAddress) -> bool>(
+        params: &SolanaTransactionParameters,
+        recipient_has_ata: F,
+    ) -> Result<Self, TransactionError> {
+        let token = match &params.token {
+            Some(token) => to_pubkey(token)?,
+            None => {
+                return Err(TransactionError::Message(
+                    "'token' is not provided".to_string(),
+                ))
+            }
+        };
+        let to = to_pubkey(&params.to)?;
+        let token_program = match &params.token_program {
+            Some(token_program) => to_pubkey(token_program)?,
+            None => id(),
+        };
+        let ata = SolanaAddress(
+            get_associated_token_address_with_program_id(&to, &token, &token_program).to_string(),
+        );
+
+        let mut params = params.clone();
+        params.has_token_account = Some(recipient_has_ata(&ata));
+        Self::new(&params)
     }
 
-    fn sign(&mut self, rs: Vec<u8>, _: u8) -> Result<Vec<u8>, TransactionError> {
-        if rs.len() != 64 {
+    /// Builds the message the fee payer signs
+    pub fn message(&self) -> Result<Message, TransactionError> {
+        let fee_payer = self.fee_payer()?;
+        let blockhash = Hash::from_str(&self.params.blockhash)
+            .map_err(|e| TransactionError::Message(format!("{}", e)))?;
+        let ixs = self.build_instructions()?;
+        Ok(Message::new_with_blockhash(
+            &ixs,
+            Some(&fee_payer),
+            &blockhash,
+        ))
+    }
+
+    /// Replaces the recent blockhash, e.g. to refresh an expired one. Any
+    /// signature is dropped as it no longer matches the message.
+    pub fn set_blockhash(&mut self, blockhash: String) {
+        self.params.blockhash = blockhash;
+        self.params.last_valid_block_height = None;
+        self.signature.clear();
+    }
+
+    /// Makes `payer` pay the fee, e.g. a relayer sponsoring a transaction
+    /// built by its user. The signer set changes, so any signature is
+    /// dropped.
+    pub fn set_fee_payer(&mut self, payer: SolanaAddress) {
+        self.params.fee_payer = match payer == self.params.from {
+            true => None,
+            false => Some(payer),
+        };
+        self.signature.clear();
+    }
+
+    /// Serializes the transaction like `to_bytes`, but fails if the
+    /// blockhash is known to have expired at `current_block_height`
+    pub fn to_bytes_at_height(
+        &self,
+        current_block_height: u64,
+    ) -> Result<Vec<u8>, TransactionError> {
+        let blockhash = self.params.recent_blockhash();
+        if blockhash.is_expired(current_block_height) == Some(true) {
             return Err(TransactionError::Message(format!(
-                "Invalid signature length {}",
-                rs.len(),
+                "Blockhash {} expired after block height {}",
+                blockhash.hash,
+                blockhash.last_valid_block_height.unwrap_or_default()
             )));
         }
-        self.signature = Some(rs);
         self.to_bytes()
     }
 
-    fn to_bytes(&self) -> Result<Vec<u8>, TransactionError> {
-        let from = Pubkey::from_str(&self.params.from.0).unwrap();
-        let to = Pubkey::from_str(&self.params.to.0).unwrap();
+    /// Serializes the unsigned message with `blockhash` in place of
+    /// `params.blockhash`, leaving this transaction untouched
+    pub fn to_bytes_with_blockhash(&self, blockhash: &str) -> Result<Vec<u8>, TransactionError> {
+        let mut tx = self.clone();
+        tx.set_blockhash(blockhash.to_string());
+        tx.to_bytes()
+    }
+
+    /// Serializes the transaction with an all-zero signature for every
+    /// required signer, for `simulateTransaction` with `sigVerify: false`
+    pub fn to_bytes_for_simulation(&self) -> Result<Vec<u8>, TransactionError> {
+        self.check_no_unknown_instructions()?;
+        let message = self.versioned_message()?;
+        let num_signatures = message.header().num_required_signatures as usize;
+        let tx = VersionedTransaction {
+            signatures: vec![Signature::default(); num_signatures],
+            message,
+        };
+        Ok(bincode::serialize(&tx).map_err(SolanaError::from)?)
+    }
+
+    /// Builds the message as a v0 message when `lookup_tables` is set and
+    /// as a legacy message otherwise
+    pub fn versioned_message(&self) -> Result<VersionedMessage, TransactionError> {
+        match &self.params.lookup_tables {
+            Some(tables) => {
+                let fee_payer = self.fee_payer()?;
+                let blockhash = Hash::from_str(&self.params.blockhash)
+                    .map_err(|e| TransactionError::Message(format!("{}", e)))?;
+                let ixs = self.build_instructions()?;
+                let tables = tables
+                    .iter()
+                    .map(|table| {
+                        Ok(AddressLookupTableAccount {
+                            key: to_pubkey(&table.address)?,
+                            addresses: table
+                                .addresses
+                                .iter()
+                                .map(to_pubkey)
+                                .collect::<Result<_, _>>()?,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, TransactionError>>()?;
+                let msg = v0::Message::try_compile(&fee_payer, &ixs, &tables, blockhash)
+                    .map_err(|e| TransactionError::Message(format!("{}", e)))?;
+                Ok(VersionedMessage::V0(msg))
+            }
+            None => Ok(VersionedMessage::Legacy(self.message()?)),
+        }
+    }
+
+    fn build_instructions(&self) -> Result<Vec<Instruction>, TransactionError> {
+        let from = to_pubkey(&self.params.from)?;
+
+        let mut ixs = match &self.params.operation {
+            Some(operation) => self.operation_instructions(operation)?,
+            None => self.transfer_instructions()?,
+        };
+        if let Some(memo) = &self.params.memo {
+            ixs.push(Instruction::new_with_bytes(
+                MEMO_PROGRAM_ID,
+                memo.as_bytes(),
+                vec![],
+            ));
+        }
+
+        if let Some(price) = self.params.compute_unit_price {
+            ixs.insert(0, ComputeBudgetInstruction::set_compute_unit_price(price));
+        }
+        if let Some(units) = self.params.compute_unit_limit {
+            ixs.insert(0, ComputeBudgetInstruction::set_compute_unit_limit(units));
+        }
+
+        if let Some(nonce_account) = &self.params.nonce_account {
+            let nonce_account = to_pubkey(nonce_account)?;
+            let nonce_authority = match &self.params.nonce_authority {
+                Some(authority) => to_pubkey(authority)?,
+                None => from,
+            };
+            ixs.insert(0, advance_nonce_account(&nonce_account, &nonce_authority));
+        }
+
+        Ok(ixs)
+    }
+
+    fn transfer_instructions(&self) -> Result<Vec<Instruction>, TransactionError> {
+        let from = to_pubkey(&self.params.from)?;
+        let to = to_pubkey(&self.params.to)?;
         let amount = self.params.amount;
-        let blockhash = Hash::from_str(&self.params.blockhash).unwrap();
 
-        let msg = match &self.params.token {
+        let ixs = match &self.params.token {
             Some(token) => {
-                let token = Pubkey::from_str(&token.0).unwrap();
-                let src = get_associated_token_address(&from, &token);
-                let dest = get_associated_token_address(&to, &token);
+                let token = to_pubkey(token)?;
+                let token_program = self.token_program()?;
+                let src =
+                    get_associated_token_address_with_program_id(&from, &token, &token_program);
+                let dest =
+                    get_associated_token_address_with_program_id(&to, &token, &token_program);
                 let decimals = match self.params.decimals {
                     Some(d) => d,
                     None => {
@@ -99,226 +1162,4208 @@ impl Transaction for SolanaTransaction {
                         ))
                     }
                 };
-                let ixs = match self.params.has_token_account {
-                    Some(true) => {
-                        let ix_transfer = token_transfer(
-                            &id(),
-                            &src,
-                            &token,
-                            &dest,
-                            &from,
-                            &[],
-                            amount,
-                            decimals,
-                        )
-                        .unwrap();
-                        vec![ix_transfer]
-                    }
-                    Some(false) => {
-                        let ix_create_account =
-                            create_associated_token_account(&from, &to, &token, &id());
-                        let ix_transfer = token_transfer(
-                            &id(),
-                            &src,
-                            &token,
-                            &dest,
-                            &from,
-                            &[],
-                            amount,
-                            decimals,
-                        )
-                        .unwrap();
+                let signers = self.multisig_signers()?;
+                let signers: Vec<&Pubkey> = signers.iter().collect();
+                let ix_transfer = match self.params.transfer_fee {
+                    Some(fee) => transfer_checked_with_fee(
+                        &token_program,
+                        &src,
+                        &token,
+                        &dest,
+                        &from,
+                        &signers,
+                        amount,
+                        decimals,
+                        fee,
+                    ),
+                    None => token_transfer(
+                        &token_program,
+                        &src,
+                        &token,
+                        &dest,
+                        &from,
+                        &signers,
+                        amount,
+                        decimals,
+                    ),
+                }
+                .map_err(program_error)?;
+                match self.params.has_token_account {
+                    Some(true) => vec![ix_transfer],
+                    Some(false) | None => {
+                        let funder = match &self.params.funder {
+                            Some(funder) => to_pubkey(funder)?,
+                            None => from,
+                        };
+                        let create = match self.params.has_token_account {
+                            Some(false) => create_associated_token_account,
+                            _ => create_associated_token_account_idempotent,
+                        };
+                        let ix_create_account = create(&funder, &to, &token, &token_program);
                         vec![ix_create_account, ix_transfer]
                     }
-                    None => {
-                        return Err(TransactionError::Message(
-                            "'has_token_account' is not provided".to_string(),
-                        ))
-                    }
-                };
-                Message::new_with_blockhash(&ixs, Some(&from), &blockhash)
-            }
-            None => {
-                let ix = sol_transfer(&from, &to, amount);
-                Message::new_with_blockhash(&[ix], Some(&from), &blockhash)
+                }
             }
+            None => vec![sol_transfer(&from, &to, amount)],
         };
+        Ok(ixs)
+    }
+
+    fn operation_instructions(
+        &self,
+        operation: &SolanaOperation,
+    ) -> Result<Vec<Instruction>, TransactionError> {
+        let from = to_pubkey(&self.params.from)?;
+        let to = to_pubkey(&self.params.to)?;
+        let amount = self.params.amount;
+        let token_program = self.token_program()?;
+        let signers = self.multisig_signers()?;
+        let signers: Vec<&Pubkey> = signers.iter().collect();
 
-        match &self.signature {
-            Some(rs) => {
-                let mut tx = Tx::new_unsigned(msg);
-                let mut sig = [0u8; 64];
-                sig.copy_from_slice(rs.as_slice());
-                tx.signatures = vec![Signature::from(sig)];
-                Ok(bincode::serialize(&tx).unwrap())
+        let ixs = match operation {
+            SolanaOperation::CreateNonceAccount { authority } => {
+                create_nonce_account(&from, &to, &to_pubkey(authority)?, amount)
+            }
+            SolanaOperation::CreateAccount {
+                lamports,
+                space,
+                owner,
+            } => vec![
+                create_account(&from, &to, *lamports, *space, &to_pubkey(owner)?),
+                sol_transfer(&from, &to, amount),
+            ],
+            SolanaOperation::CreateAccountWithSeed {
+                base,
+                seed,
+                space,
+                owner,
+            } => vec![create_account_with_seed(
+                &from,
+                &to,
+                &to_pubkey(base)?,
+                seed,
+                amount,
+                *space,
+                &to_pubkey(owner)?,
+            )],
+            SolanaOperation::Instructions { instructions } => instructions
+                .iter()
+                .map(SolanaCustomInstruction::to_instruction)
+                .collect::<Result<_, _>>()?,
+            SolanaOperation::Allocate { space } => vec![allocate(&from, *space)],
+            SolanaOperation::Assign { owner } => vec![assign(&from, &to_pubkey(owner)?)],
+            SolanaOperation::Approve => {
+                let token = self.token()?;
+                let decimals = self.decimals()?;
+                let src =
+                    get_associated_token_address_with_program_id(&from, &token, &token_program);
+                vec![approve_checked(
+                    &token_program,
+                    &src,
+                    &token,
+                    &to,
+                    &from,
+                    &signers,
+                    amount,
+                    decimals,
+                )
+                .map_err(program_error)?]
+            }
+            SolanaOperation::TransferWithSeed { seed, owner } => {
+                let owner = to_pubkey(owner)?;
+                let source = Pubkey::create_with_seed(&from, seed, &owner)
+                    .map_err(|e| TransactionError::Message(format!("{}", e)))?;
+                vec![transfer_with_seed(
+                    &source,
+                    &from,
+                    seed.clone(),
+                    &owner,
+                    &to,
+                    amount,
+                )]
+            }
+            SolanaOperation::MultiTransfer { recipients } => match &self.params.token {
+                Some(_) => {
+                    let token = self.token()?;
+                    let decimals = self.decimals()?;
+                    let src =
+                        get_associated_token_address_with_program_id(&from, &token, &token_program);
+                    recipients
+                        .iter()
+                        .map(|recipient| {
+                            let dest = get_associated_token_address_with_program_id(
+                                &to_pubkey(&recipient.to)?,
+                                &token,
+                                &token_program,
+                            );
+                            token_transfer(
+                                &token_program,
+                                &src,
+                                &token,
+                                &dest,
+                                &from,
+                                &signers,
+                                recipient.amount,
+                                decimals,
+                            )
+                            .map_err(program_error)
+                        })
+                        .collect::<Result<_, _>>()?
+                }
+                None => recipients
+                    .iter()
+                    .map(|recipient| {
+                        Ok(sol_transfer(
+                            &from,
+                            &to_pubkey(&recipient.to)?,
+                            recipient.amount,
+                        ))
+                    })
+                    .collect::<Result<_, TransactionError>>()?,
+            },
+            SolanaOperation::TokenBatch { transfers } => transfers
+                .iter()
+                .map(|transfer| {
+                    let token = to_pubkey(&transfer.token)?;
+                    let src =
+                        get_associated_token_address_with_program_id(&from, &token, &token_program);
+                    token_transfer(
+                        &token_program,
+                        &src,
+                        &token,
+                        &to_pubkey(&transfer.to)?,
+                        &from,
+                        &signers,
+                        transfer.amount,
+                        transfer.decimals,
+                    )
+                    .map_err(program_error)
+                })
+                .collect::<Result<_, _>>()?,
+            SolanaOperation::WrapSol => vec![
+                sol_transfer(&from, &to, amount),
+                sync_native(&token_program, &to).map_err(program_error)?,
+            ],
+            SolanaOperation::MintTo => {
+                let token = self.token()?;
+                let decimals = self.decimals()?;
+                vec![mint_to_checked(
+                    &token_program,
+                    &token,
+                    &to,
+                    &from,
+                    &signers,
+                    amount,
+                    decimals,
+                )
+                .map_err(program_error)?]
+            }
+            SolanaOperation::Revoke => {
+                vec![revoke(&token_program, &to, &from, &signers).map_err(program_error)?]
+            }
+            SolanaOperation::FreezeAccount => {
+                vec![
+                    freeze_account(&token_program, &to, &self.token()?, &from, &signers)
+                        .map_err(program_error)?,
+                ]
+            }
+            SolanaOperation::ThawAccount => {
+                vec![
+                    thaw_account(&token_program, &to, &self.token()?, &from, &signers)
+                        .map_err(program_error)?,
+                ]
+            }
+            SolanaOperation::DelegateStake { vote_account } => {
+                vec![delegate_stake(&to, &from, &to_pubkey(vote_account)?)]
+            }
+            SolanaOperation::DeactivateStake => vec![deactivate_stake(&to, &from)],
+            SolanaOperation::UncheckedTokenTransfer { source } => {
+                // Only rebuilt from parsed transactions
+                #[allow(deprecated)]
+                let ix = spl_token_2022::instruction::transfer(
+                    &token_program,
+                    &to_pubkey(source)?,
+                    &to,
+                    &from,
+                    &signers,
+                    amount,
+                )
+                .map_err(program_error)?;
+                vec![ix]
+            }
+            SolanaOperation::SetAuthority {
+                authority_type,
+                new_authority,
+            } => {
+                let new_authority = new_authority.as_ref().map(to_pubkey).transpose()?;
+                vec![set_authority(
+                    &token_program,
+                    &to,
+                    new_authority.as_ref(),
+                    (*authority_type).into(),
+                    &from,
+                    &signers,
+                )
+                .map_err(program_error)?]
             }
-            None => Ok(msg.serialize()),
+        };
+        Ok(ixs)
+    }
+
+    /// Fails if `from_bytes` set instructions aside, which a message rebuilt
+    /// from the params would silently drop
+    fn check_no_unknown_instructions(&self) -> Result<(), TransactionError> {
+        match self.unknown_instructions.first() {
+            Some((program, _, _)) => Err(TransactionError::Message(format!(
+                "Transaction has an instruction of unsupported program {} and can't be rebuilt",
+                program
+            ))),
+            None => Ok(()),
         }
     }
 
-    fn from_bytes(tx: &[u8]) -> Result<Self, TransactionError> {
-        let tx = bincode::deserialize::<Tx>(tx)
-            .map_err(|e| TransactionError::Message(format!("{}", e)))?;
+    fn fee_payer(&self) -> Result<Pubkey, TransactionError> {
+        match &self.params.fee_payer {
+            Some(fee_payer) => to_pubkey(fee_payer),
+            None => to_pubkey(&self.params.from),
+        }
+    }
+
+    fn token(&self) -> Result<Pubkey, TransactionError> {
+        match &self.params.token {
+            Some(token) => to_pubkey(token),
+            None => Err(TransactionError::Message(
+                "'token' is not provided".to_string(),
+            )),
+        }
+    }
+
+    fn token_program(&self) -> Result<Pubkey, TransactionError> {
+        match &self.params.token_program {
+            Some(token_program) => to_pubkey(token_program),
+            None => Ok(id()),
+        }
+    }
 
-        let sig = if !tx.signatures.is_empty() {
-            let rs = tx.signatures[0];
-            let mut sig = [0u8; 64];
-            sig.copy_from_slice(rs.as_ref());
-            Some(sig.to_vec())
+    fn multisig_signers(&self) -> Result<Vec<Pubkey>, TransactionError> {
+        match &self.params.multisig_signers {
+            Some(signers) => signers.iter().map(to_pubkey).collect(),
+            None => Ok(vec![]),
+        }
+    }
+
+    fn decimals(&self) -> Result<u8, TransactionError> {
+        self.params.decimals.ok_or(TransactionError::Message(
+            "'decimal' is not provided".to_string(),
+        ))
+    }
+
+    /// Checks the token decimals against `expected`, the decimals of the
+    /// mint as known to the caller. A `TransferChecked` carrying other
+    /// decimals than its mint's fails on chain, or points at a forged
+    /// transaction.
+    pub fn verify_token_decimals(&self, expected: u8) -> Result<(), TransactionError> {
+        let decimals = self.decimals()?;
+        if decimals != expected {
+            return Err(TransactionError::Message(format!(
+                "Token decimals mismatch: transaction has {}, mint has {}",
+                decimals, expected
+            )));
+        }
+        Ok(())
+    }
+
+    /// Parses a base64 encoded transaction, the default encoding of the
+    /// `getTransaction` and `sendTransaction` RPC methods
+    pub fn from_base64(tx: &str) -> Result<Self, TransactionError> {
+        Ok(SolanaTransaction::try_from_base64(tx)?)
+    }
+
+    /// Parses like `from_bytes`, returning a `SolanaError` to match on
+    pub fn try_from_bytes(tx: &[u8]) -> Result<Self, SolanaError> {
+        // `VersionedTransaction` understands both legacy and v0 transactions
+        let tx = bincode::deserialize::<VersionedTransaction>(tx)?;
+        Ok(SolanaTransaction::try_from(tx)?)
+    }
+
+    /// Parses like `from_str`, returning a `SolanaError` to match on
+    pub fn try_from_base58(tx: &str) -> Result<Self, SolanaError> {
+        SolanaTransaction::try_from_bytes(&bs58::decode(tx).into_vec()?)
+    }
+
+    /// Parses like `from_base64`, returning a `SolanaError` to match on
+    pub fn try_from_base64(tx: &str) -> Result<Self, SolanaError> {
+        SolanaTransaction::try_from_bytes(&BASE64_STANDARD.decode(tx)?)
+    }
+
+    /// Returns the serialized transaction encoded in base64
+    pub fn to_base64(&self) -> Result<String, TransactionError> {
+        Ok(BASE64_STANDARD.encode(self.to_bytes()?))
+    }
+
+    /// Returns the serialized transaction encoded in base58, the inverse of
+    /// `from_str`
+    pub fn to_base58(&self) -> Result<String, TransactionError> {
+        Ok(bs58::encode(self.to_bytes()?).into_string())
+    }
+
+    /// Checks that rebuilding this transaction from its params reproduces
+    /// `original`, the bytes it was parsed from. `from_bytes` is lossy, so a
+    /// transaction with unusual account ordering or extra instructions can't
+    /// be faithfully rebuilt.
+    pub fn assert_roundtrips(&self, original: &[u8]) -> Result<(), TransactionError> {
+        let rebuilt = self.to_bytes()?;
+        if rebuilt == original {
+            return Ok(());
+        }
+
+        let original = bincode::deserialize::<VersionedTransaction>(original)
+            .map_err(|e| TransactionError::Message(format!("{}", e)))?
+            .message;
+        let msg = self.versioned_message()?;
+        let version = |msg: &VersionedMessage| match msg {
+            VersionedMessage::Legacy(_) => "legacy",
+            VersionedMessage::V0(_) => "v0",
+        };
+        let reason = if version(&original) != version(&msg) {
+            format!(
+                "version differs: original {}, rebuilt {}",
+                version(&original),
+                version(&msg)
+            )
+        } else if original.header() != msg.header() {
+            format!(
+                "header differs: original {:?}, rebuilt {:?}",
+                original.header(),
+                msg.header()
+            )
+        } else if original.static_account_keys() != msg.static_account_keys() {
+            format!(
+                "account keys differ: original {:?}, rebuilt {:?}",
+                original.static_account_keys(),
+                msg.static_account_keys()
+            )
+        } else if original.recent_blockhash() != msg.recent_blockhash() {
+            format!(
+                "blockhash differs: original {}, rebuilt {}",
+                original.recent_blockhash(),
+                msg.recent_blockhash()
+            )
+        } else if original.instructions() != msg.instructions() {
+            format!(
+                "instructions differ: original {:?}, rebuilt {:?}",
+                original.instructions(),
+                msg.instructions()
+            )
+        } else if original.address_table_lookups() != msg.address_table_lookups() {
+            format!(
+                "address table lookups differ: original {:?}, rebuilt {:?}",
+                original.address_table_lookups(),
+                msg.address_table_lookups()
+            )
         } else {
-            None
+            "signatures differ".to_string()
+        };
+        Err(TransactionError::Message(format!(
+            "Transaction does not round trip, {}",
+            reason
+        )))
+    }
+
+    /// Returns the length of the transaction once signed by all required
+    /// signers, whether or not signatures are attached yet
+    pub fn size_bytes(&self) -> Result<usize, TransactionError> {
+        let message = self.versioned_message()?;
+        let tx = VersionedTransaction {
+            signatures: vec![
+                Signature::default();
+                message.header().num_required_signatures as usize
+            ],
+            message,
+        };
+        bincode::serialized_size(&tx)
+            .map(|size| size as usize)
+            .map_err(|e| TransactionError::Message(format!("{}", e)))
+    }
+
+    /// Returns true if the signed transaction fits in the `PACKET_DATA_SIZE`
+    /// (1232 bytes) limit
+    pub fn fits_in_packet(&self) -> bool {
+        self.size_bytes()
+            .map(|size| size <= PACKET_DATA_SIZE)
+            .unwrap_or(false)
+    }
+
+    /// Returns the lamports leaving `from`: the SOL it transfers or funds
+    /// accounts with, `ata_rent` for each associated token account it
+    /// creates, idempotently or not, and the fee if it is the fee payer.
+    /// The priority fee is only counted when the compute unit limit is set.
+    pub fn total_debit(
+        &self,
+        lamports_per_signature: u64,
+        ata_rent: u64,
+    ) -> Result<u64, TransactionError> {
+        let from = to_pubkey(&self.params.from)?;
+        let overflow = || TransactionError::Message("Total debit overflows u64".to_string());
+
+        let mut total = 0u64;
+        for ix in self.build_instructions()? {
+            let funder = ix.accounts.first().map(|meta| meta.pubkey);
+            if funder != Some(from) {
+                continue;
+            }
+            let lamports = if ix.program_id == SYSTEM_PROGRAM_ID {
+                match bincode::deserialize::<SystemInstruction>(&ix.data) {
+                    Ok(SystemInstruction::Transfer { lamports })
+                    | Ok(SystemInstruction::CreateAccount { lamports, .. })
+                    | Ok(SystemInstruction::CreateAccountWithSeed { lamports, .. }) => lamports,
+                    _ => 0,
+                }
+            } else if ix.program_id == ASSOCIATED_TOKEN_PROGRAM_ID {
+                ata_rent
+            } else {
+                0
+            };
+            total = total.checked_add(lamports).ok_or_else(overflow)?;
+        }
+
+        if self.fee_payer()? == from {
+            let signatures = self.message()?.header.num_required_signatures as u64;
+            let fee = signatures
+                .checked_mul(lamports_per_signature)
+                .ok_or_else(overflow)?;
+            let priority_fee = match (
+                self.params.compute_unit_limit,
+                self.params.compute_unit_price,
+            ) {
+                (Some(units), Some(price)) => (units as u128 * price as u128).div_ceil(1_000_000),
+                _ => 0,
+            };
+            let priority_fee = u64::try_from(priority_fee).map_err(|_| overflow())?;
+            total = total
+                .checked_add(fee)
+                .and_then(|total| total.checked_add(priority_fee))
+                .ok_or_else(overflow)?;
+        }
+        Ok(total)
+    }
+
+    /// Returns true if both transactions have the same params, whether or
+    /// not they are signed
+    pub fn same_params(&self, other: &Self) -> bool {
+        self.params == other.params
+    }
+
+    /// Returns true if a signature has been attached
+    pub fn is_signed(&self) -> bool {
+        !self.signature.is_empty()
+    }
+
+    /// Records the signature of `signer`, one of `required_signers`, in its
+    /// slot. Signers that have not signed yet keep an all-zero placeholder,
+    /// so signatures can be collected one at a time in any order.
+    pub fn add_signature(
+        &mut self,
+        signer: SolanaAddress,
+        signature: Vec<u8>,
+    ) -> Result<(), TransactionError> {
+        check_signature(&signature)?;
+        let signers = self.required_signers()?;
+        let index = signers.iter().position(|s| *s == signer).ok_or_else(|| {
+            TransactionError::Message(format!("{} is not a required signer", signer))
+        })?;
+        if self.signature.len() < signers.len() {
+            self.signature.resize(signers.len(), vec![0u8; 64]);
+        }
+        self.signature[index] = signature;
+        Ok(())
+    }
+
+    /// Replaces the signatures with `signatures`, keyed by signer and put in
+    /// the order of `required_signers`. Signers missing from the map get an
+    /// all-zero placeholder.
+    pub fn set_signatures(
+        &mut self,
+        signatures: &HashMap<SolanaAddress, Vec<u8>>,
+    ) -> Result<(), TransactionError> {
+        let signers = self.required_signers()?;
+        for (signer, signature) in signatures {
+            if !signers.contains(signer) {
+                return Err(TransactionError::Message(format!(
+                    "{} is not a required signer",
+                    signer
+                )));
+            }
+            check_signature(signature)?;
+        }
+        self.signature = order_signatures(&signers, signatures);
+        Ok(())
+    }
+
+    /// Checks the fee payer's signature against the message built from the
+    /// params, catching a signature over the wrong message or by the wrong
+    /// key before broadcasting
+    pub fn verify(&self) -> Result<(), TransactionError> {
+        let signature = self
+            .signature
+            .first()
+            .ok_or_else(|| TransactionError::Message("Transaction is not signed".to_string()))?;
+        let signature = Signature::try_from(signature.as_slice())
+            .map_err(|e| TransactionError::Message(format!("{}", e)))?;
+        let fee_payer = self.fee_payer()?;
+        let message = self.versioned_message()?.serialize();
+        if !signature.verify(fee_payer.as_ref(), &message) {
+            return Err(TransactionError::Message(format!(
+                "Signature {} is not a signature of fee payer {} over the message",
+                signature, fee_payer
+            )));
+        }
+        Ok(())
+    }
+
+    /// Returns true if every required signer has attached its signature
+    pub fn is_fully_signed(&self) -> bool {
+        match self.required_signers() {
+            Ok(signers) => {
+                self.signature.len() == signers.len()
+                    && self.signature.iter().all(|sig| sig.iter().any(|b| *b != 0))
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Serializes the transaction once every required signer has signed
+    pub fn finalize(&self) -> Result<Vec<u8>, TransactionError> {
+        if !self.is_fully_signed() {
+            return Err(TransactionError::Message(
+                "Transaction is missing signatures".to_string(),
+            ));
+        }
+        self.to_bytes()
+    }
+
+    /// Returns a copy without the signature, e.g. to sign it again after
+    /// refreshing the blockhash
+    pub fn clone_unsigned(&self) -> SolanaTransaction {
+        SolanaTransaction {
+            signature: vec![],
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy carrying `signature`, the inverse of `clone_unsigned`
+    pub fn with_signature(
+        &self,
+        signature: Vec<u8>,
+    ) -> Result<SolanaTransaction, TransactionError> {
+        let mut tx = self.clone();
+        tx.sign(signature, 0)?;
+        Ok(tx)
+    }
+
+    /// Returns the fee payer's signature in base58, the canonical Solana
+    /// encoding
+    pub fn signature_base58(&self) -> Option<String> {
+        self.signature
+            .first()
+            .map(|sig| bs58::encode(sig).into_string())
+    }
+
+    /// Returns the accounts that must sign the transaction, in signature order
+    pub fn required_signers(&self) -> Result<Vec<SolanaAddress>, TransactionError> {
+        let msg = self.message()?;
+        let num_signers = msg.header.num_required_signatures as usize;
+        Ok(msg.account_keys[..num_signers]
+            .iter()
+            .map(|key| SolanaAddress(key.to_string()))
+            .collect())
+    }
+
+    /// Builds a transaction of fully-formed instructions paid by
+    /// `fee_payer`, bypassing the params-to-instruction mapping.
+    /// `from_bytes` still parses it like any other transaction.
+    pub fn from_instructions(
+        ixs: Vec<Instruction>,
+        fee_payer: SolanaAddress,
+        blockhash: String,
+    ) -> Result<Self, TransactionError> {
+        let params = SolanaTransactionParameters {
+            from: fee_payer.clone(),
+            to: fee_payer,
+            blockhash,
+            operation: Some(SolanaOperation::Instructions {
+                instructions: ixs.into_iter().map(SolanaCustomInstruction::from).collect(),
+            }),
+            ..Default::default()
+        };
+        SolanaTransaction::new(&params)
+    }
+
+    /// Builds a transaction of typed `instructions`, such as those returned
+    /// by `instructions`, paid by `fee_payer`. `Other` instructions lose
+    /// their signer and writable flags, see `SolanaInstruction::to_instruction`.
+    pub fn from_typed_instructions(
+        instructions: &[SolanaInstruction],
+        fee_payer: SolanaAddress,
+        blockhash: String,
+    ) -> Result<Self, TransactionError> {
+        let ixs = instructions
+            .iter()
+            .map(SolanaInstruction::to_instruction)
+            .collect::<Result<_, _>>()?;
+        Self::from_instructions(ixs, fee_payer, blockhash)
+    }
+
+    /// Parses a base64 serialized message, legacy or v0, such as the
+    /// unsigned output of `to_bytes`, into an unsigned transaction
+    pub fn from_message_base64(message: &str) -> Result<Self, TransactionError> {
+        let message = BASE64_STANDARD
+            .decode(message)
+            .map_err(|e| TransactionError::Message(format!("{}", e)))?;
+        let message = bincode::deserialize::<VersionedMessage>(&message)
+            .map_err(|e| TransactionError::Message(format!("{}", e)))?;
+        SolanaTransaction::try_from(VersionedTransaction {
+            signatures: vec![],
+            message,
+        })
+    }
+
+    /// Parses like `from_bytes`, but fails on any instruction it would set
+    /// aside instead, so that the result re-serializes. Beyond System, SPL
+    /// Token and Associated Token Account, that allows the compute unit
+    /// limit and price and a trailing memo without signers.
+    pub fn from_bytes_strict(tx: &[u8]) -> Result<Self, TransactionError> {
+        let tx = SolanaTransaction::from_bytes(tx)?;
+        if let Some((program, _, _)) = tx.unknown_instructions.first() {
+            return Err(TransactionError::Message(format!(
+                "Unsupported instruction of program {}",
+                program
+            )));
+        }
+        Ok(tx)
+    }
+
+    /// Returns every account key of the message as `(address, is_signer,
+    /// is_writable)`, the flags being derived from the message header
+    pub fn decoded_accounts(&self) -> Result<Vec<(SolanaAddress, bool, bool)>, TransactionError> {
+        let msg = self.versioned_message()?;
+        let header = msg.header();
+        let keys = msg.static_account_keys();
+        let num_signers = header.num_required_signatures as usize;
+        let num_writable_signers =
+            num_signers.saturating_sub(header.num_readonly_signed_accounts as usize);
+        let num_writable_unsigned = keys
+            .len()
+            .saturating_sub(header.num_readonly_unsigned_accounts as usize);
+        Ok(keys
+            .iter()
+            .enumerate()
+            .map(|(i, key)| {
+                let is_signer = i < num_signers;
+                let is_writable = if is_signer {
+                    i < num_writable_signers
+                } else {
+                    i < num_writable_unsigned
+                };
+                (SolanaAddress(key.to_string()), is_signer, is_writable)
+            })
+            .collect())
+    }
+
+    /// Returns the instructions of the message built from the params,
+    /// followed by the unrecognized instructions found by `from_bytes`
+    pub fn instructions(&self) -> Result<Vec<SolanaInstruction>, TransactionError> {
+        let (keys, ixs) = match self.versioned_message()? {
+            VersionedMessage::Legacy(msg) => (msg.account_keys, msg.instructions),
+            VersionedMessage::V0(msg) => (msg.account_keys, msg.instructions),
+        };
+        let mut instructions = ixs
+            .iter()
+            .map(|ix| SolanaInstruction::decode(&keys, ix))
+            .collect::<Result<Vec<_>, _>>()?;
+        instructions.extend(
+            self.unknown_instructions
+                .iter()
+                .cloned()
+                .map(SolanaInstruction::Other),
+        );
+        Ok(instructions)
+    }
+
+    /// Returns the program, number of accounts and data length of each
+    /// instruction, in the same order as `instructions`
+    pub fn instruction_summaries(&self) -> Result<Vec<SolanaInstructionSummary>, TransactionError> {
+        let (keys, ixs) = match self.versioned_message()? {
+            VersionedMessage::Legacy(msg) => (msg.account_keys, msg.instructions),
+            VersionedMessage::V0(msg) => (msg.account_keys, msg.instructions),
+        };
+        let mut summaries = ixs
+            .iter()
+            .map(|ix| {
+                Ok(SolanaInstructionSummary {
+                    program: SolanaAddress(program_id(&keys, ix)?.to_string()),
+                    account_count: ix.accounts.len(),
+                    data_len: ix.data.len(),
+                })
+            })
+            .collect::<Result<Vec<_>, TransactionError>>()?;
+        summaries.extend(
+            self.unknown_instructions
+                .iter()
+                .map(|(program, accounts, data)| SolanaInstructionSummary {
+                    program: program.clone(),
+                    account_count: accounts.len(),
+                    data_len: data.len(),
+                }),
+        );
+        Ok(summaries)
+    }
+
+    /// Returns a canonical JSON rendering (sorted keys, decoded instructions)
+    /// of the message that will be signed, for display in approval workflows
+    pub fn approval_json(&self) -> Result<String, TransactionError> {
+        self.check_no_unknown_instructions()?;
+        let msg = self.versioned_message()?;
+        let keys = self.loaded_account_keys(&msg)?;
+        let instructions = msg
+            .instructions()
+            .iter()
+            .map(|ix| {
+                let program = program_id(&keys, ix)?;
+                let accounts = ix
+                    .accounts
+                    .iter()
+                    .map(|index| {
+                        Ok(json!({
+                            "pubkey": account_key(&keys, *index)?.to_string(),
+                            "is_signer": msg.is_signer(*index as usize),
+                            "is_writable": msg.is_maybe_writable(*index as usize, None),
+                        }))
+                    })
+                    .collect::<Result<Vec<_>, TransactionError>>()?;
+                let decoded = approval_decoded(&program, &ix.data);
+                Ok(json!({
+                    "program": program.to_string(),
+                    "accounts": accounts,
+                    "data": hex::encode(&ix.data),
+                    "decoded": decoded,
+                }))
+            })
+            .collect::<Result<Vec<_>, TransactionError>>()?;
+        let mut value = json!({
+            "fee_payer": account_key(&keys, 0)?.to_string(),
+            "recent_blockhash": msg.recent_blockhash().to_string(),
+            "instructions": instructions,
+        });
+        if let Some(lookups) = msg.address_table_lookups() {
+            let lookups = lookups
+                .iter()
+                .map(|lookup| {
+                    json!({
+                        "account_key": lookup.account_key.to_string(),
+                        "writable_indexes": lookup.writable_indexes,
+                        "readonly_indexes": lookup.readonly_indexes,
+                    })
+                })
+                .collect::<Vec<_>>();
+            value["address_table_lookups"] = json!(lookups);
+        }
+        // serde_json maps keep their keys sorted, which makes the output canonical
+        serde_json::to_string(&value).map_err(|e| TransactionError::Message(format!("{}", e)))
+    }
+
+    /// Returns the static account keys of `msg` followed by the addresses its
+    /// lookups load from `lookup_tables`, the writable ones first, which is
+    /// how instructions of a v0 message index them
+    fn loaded_account_keys(&self, msg: &VersionedMessage) -> Result<Vec<Pubkey>, TransactionError> {
+        let mut keys = msg.static_account_keys().to_vec();
+        let lookups = msg.address_table_lookups().unwrap_or_default();
+        let tables = self.params.lookup_tables.as_deref().unwrap_or_default();
+        let resolve = |lookup: &MessageAddressTableLookup, index: u8| {
+            let table = tables
+                .iter()
+                .find(|table| table.address.0 == lookup.account_key.to_string())
+                .ok_or_else(|| {
+                    TransactionError::Message(format!(
+                        "Lookup table {} is not provided",
+                        lookup.account_key
+                    ))
+                })?;
+            let address = table.addresses.get(index as usize).ok_or_else(|| {
+                TransactionError::Message(format!(
+                    "Lookup table {} has no address at index {}",
+                    lookup.account_key, index
+                ))
+            })?;
+            to_pubkey(address)
+        };
+        for lookup in lookups {
+            for index in &lookup.writable_indexes {
+                keys.push(resolve(lookup, *index)?);
+            }
+        }
+        for lookup in lookups {
+            for index in &lookup.readonly_indexes {
+                keys.push(resolve(lookup, *index)?);
+            }
+        }
+        Ok(keys)
+    }
+
+    /// Returns the SHA-256 hex digest of `approval_json`, binding an approval
+    /// to exactly the message that will be signed
+    pub fn approval_digest(&self) -> Result<String, TransactionError> {
+        let json = self.approval_json()?;
+        Ok(hex::encode(hash(json.as_bytes()).to_bytes()))
+    }
+
+    /// Exports the message to be signed offline, base58 encoded
+    pub fn export_unsigned(&self) -> Result<String, TransactionError> {
+        self.check_no_unknown_instructions()?;
+        Ok(bs58::encode(self.versioned_message()?.serialize()).into_string())
+    }
+
+    /// Attaches a signature made offline over `message_b58`, the output of
+    /// `export_unsigned`, after checking that the message is the one built
+    /// from this transaction's params. Returns the signed transaction.
+    pub fn import_and_sign(
+        &mut self,
+        message_b58: &str,
+        signature: Vec<u8>,
+    ) -> Result<Vec<u8>, TransactionError> {
+        let message = bs58::decode(message_b58)
+            .into_vec()
+            .map_err(|e| TransactionError::Message(format!("{}", e)))?;
+        if message != self.versioned_message()?.serialize() {
+            return Err(TransactionError::Message(
+                "Imported message does not match the transaction".to_string(),
+            ));
+        }
+        self.sign(signature, 0)
+    }
+
+    /// Returns the SHA-256 of the serialized unsigned message. Unlike the
+    /// derived `Hash` it ignores the signature, so it can key a dedup cache
+    /// of pending transactions.
+    pub fn content_id(&self) -> Result<[u8; 32], TransactionError> {
+        Ok(hash(&self.versioned_message()?.serialize()).to_bytes())
+    }
+
+    /// Like `new`, but accepts the zero amounts a transaction on chain may
+    /// carry
+    fn parsed(params: &SolanaTransactionParameters) -> Result<Self, TransactionError> {
+        params.validate_structure()?;
+        Ok(SolanaTransaction {
+            params: params.clone(),
+            signature: vec![],
+            unknown_instructions: vec![],
+        })
+    }
+}
+
+impl Transaction for SolanaTransaction {
+    type Address = SolanaAddress;
+    type Format = SolanaFormat;
+    type PublicKey = SolanaPublicKey;
+    type TransactionParameters = SolanaTransactionParameters;
+    type TransactionId = SolanaTransactionId;
+
+    fn new(params: &Self::TransactionParameters) -> Result<Self, TransactionError> {
+        params.validate()?;
+        Ok(SolanaTransaction {
+            params: params.clone(),
+            signature: vec![],
+            unknown_instructions: vec![],
+        })
+    }
+
+    /// Attaches the fee payer's signature, keeping those of other signers
+    fn sign(&mut self, rs: Vec<u8>, _: u8) -> Result<Vec<u8>, TransactionError> {
+        check_signature(&rs)?;
+        match self.signature.first_mut() {
+            Some(sig) => *sig = rs,
+            None => self.signature.push(rs),
+        }
+        self.to_bytes()
+    }
+
+    fn to_bytes(&self) -> Result<Vec<u8>, TransactionError> {
+        self.check_no_unknown_instructions()?;
+        let msg = self.versioned_message()?;
+
+        if self.signature.is_empty() {
+            return Ok(msg.serialize());
+        }
+        let tx = VersionedTransaction {
+            signatures: to_signatures(&self.signature)?,
+            message: msg,
+        };
+        bincode::serialize(&tx).map_err(|e| TransactionError::Message(format!("{}", e)))
+    }
+
+    fn from_bytes(tx: &[u8]) -> Result<Self, TransactionError> {
+        Ok(SolanaTransaction::try_from_bytes(tx)?)
+    }
+
+    fn to_transaction_id(&self) -> Result<Self::TransactionId, TransactionError> {
+        match self.signature.first() {
+            Some(sig) => {
+                let txid: [u8; 64] = sig.as_slice().try_into().map_err(|_| {
+                    TransactionError::Message(format!("Invalid signature length {}", sig.len()))
+                })?;
+                Ok(SolanaTransactionId(txid))
+            }
+            None => Err(TransactionError::Message(
+                "Transaction is not signed".to_string(),
+            )),
+        }
+    }
+}
+
+impl TryFrom<VersionedTransaction> for SolanaTransaction {
+    type Error = TransactionError;
+
+    fn try_from(tx: VersionedTransaction) -> Result<Self, Self::Error> {
+        let sig: Vec<Vec<u8>> = tx
+            .signatures
+            .iter()
+            .map(|sig| sig.as_ref().to_vec())
+            .collect();
+
+        let is_v0 = matches!(tx.message, VersionedMessage::V0(_));
+        let (keys, blockhash, instructions) = match tx.message {
+            VersionedMessage::Legacy(msg) => {
+                (msg.account_keys, msg.recent_blockhash, msg.instructions)
+            }
+            VersionedMessage::V0(msg) => {
+                if !msg.address_table_lookups.is_empty() {
+                    return Err(TransactionError::Message(
+                        "Address table lookups are not supported".to_string(),
+                    ));
+                }
+                (msg.account_keys, msg.recent_blockhash, msg.instructions)
+            }
         };
 
-        let keys = tx.message.account_keys;
-        let ixs = tx.message.instructions;
-        let blockhash = tx.message.recent_blockhash;
+        // Set aside instructions of programs we don't model, only the
+        // remaining ones have to form a recognized transaction
+        let mut ixs = vec![];
+        let mut unknown_instructions: Vec<SolanaRawInstruction> = vec![];
+        let mut compute_unit_limit = None;
+        let mut compute_unit_price = None;
+        let mut memo = None;
+        let num_instructions = instructions.len();
+        for (i, ix) in instructions.into_iter().enumerate() {
+            let program = program_id(&keys, &ix)?;
+            // Only a trailing memo without signers is rebuilt from `memo`
+            if program == MEMO_PROGRAM_ID && i + 1 == num_instructions && ix.accounts.is_empty() {
+                if let SolanaInstruction::Memo { memo: text } =
+                    SolanaInstruction::decode(&keys, &ix)?
+                {
+                    memo = Some(text);
+                    continue;
+                }
+            }
+            if program == COMPUTE_BUDGET_PROGRAM_ID {
+                match SolanaInstruction::decode(&keys, &ix)? {
+                    SolanaInstruction::ComputeUnitLimit { units } => {
+                        compute_unit_limit = Some(units);
+                        continue;
+                    }
+                    SolanaInstruction::ComputeUnitPrice { micro_lamports } => {
+                        compute_unit_price = Some(micro_lamports);
+                        continue;
+                    }
+                    _ => {}
+                }
+            }
+            if program == SYSTEM_PROGRAM_ID
+                || program == SPL_TOKEN_PROGRAM_ID
+                || program == TOKEN_2022_PROGRAM_ID
+                || program == ASSOCIATED_TOKEN_PROGRAM_ID
+                || program == STAKE_PROGRAM_ID
+            {
+                ixs.push(ix);
+            } else {
+                let accounts = ix
+                    .accounts
+                    .iter()
+                    .map(|index| Ok(SolanaAddress(account_key(&keys, *index)?.to_string())))
+                    .collect::<Result<_, TransactionError>>()?;
+                unknown_instructions.push((SolanaAddress(program.to_string()), accounts, ix.data));
+            }
+        }
+
+        // A durable-nonce transaction starts with `AdvanceNonceAccount`,
+        // the instructions after it are parsed as usual
+        let mut nonce = None;
+        if let Some(ix) = ixs.first() {
+            if program_id(&keys, ix)? == SYSTEM_PROGRAM_ID
+                && matches!(
+                    bincode::deserialize::<SystemInstruction>(&ix.data),
+                    Ok(SystemInstruction::AdvanceNonceAccount)
+                )
+            {
+                let nonce_account = instruction_account(&keys, ix, 0)?;
+                let nonce_authority = instruction_account(&keys, ix, 2)?;
+                nonce = Some((nonce_account, nonce_authority));
+                ixs.remove(0);
+            }
+        }
 
-        match ixs.len() {
+        let mut tx = match ixs.len() {
             1 => {
-                let program = keys[ixs[0].program_id_index as usize];
-                let account = &ixs[0].accounts;
+                let program = program_id(&keys, &ixs[0])?;
                 let data = &ixs[0].data;
-                match format!("{}", program).as_str() {
-                    "11111111111111111111111111111111" => {
-                        let from = keys[account[0] as usize];
-                        let to = keys[account[1] as usize];
-
+                match program {
+                    SYSTEM_PROGRAM_ID => {
                         let ix = bincode::deserialize::<SystemInstruction>(data)
                             .map_err(|e| TransactionError::Message(format!("{}", e)))?;
 
                         match ix {
                             SystemInstruction::Transfer { lamports } => {
+                                let from = instruction_account(&keys, &ixs[0], 0)?;
+                                let to = instruction_account(&keys, &ixs[0], 1)?;
                                 let params = SolanaTransactionParameters {
-                                    token: None,
-                                    has_token_account: None,
-                                    decimals: None,
                                     from: SolanaAddress(from.to_string()),
                                     to: SolanaAddress(to.to_string()),
                                     amount: lamports,
                                     blockhash: blockhash.to_string(),
+                                    ..Default::default()
                                 };
-                                let mut tx = SolanaTransaction::new(&params)?;
+                                let mut tx = SolanaTransaction::parsed(&params)?;
                                 tx.signature = sig;
                                 Ok(tx)
                             }
-                            _ => Err(TransactionError::Message(format!(
-                                "Unsupported system instruction: {:?}",
-                                ix
-                            ))),
-                        }
-                    }
-                    "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA" => {
-                        let token = keys[account[1] as usize];
-                        let dest = keys[account[2] as usize];
-                        let from = keys[account[3] as usize];
-
-                        let ix = TokenInstruction::unpack(data)
-                            .map_err(|e| TransactionError::Message(format!("{}", e)))?;
-
-                        match ix {
-                            TokenInstruction::TransferChecked { amount, decimals } => {
+                            SystemInstruction::TransferWithSeed {
+                                lamports,
+                                from_seed,
+                                from_owner,
+                            } => {
+                                // accounts are [derived source, base, recipient]
+                                let base = instruction_account(&keys, &ixs[0], 1)?;
+                                let to = instruction_account(&keys, &ixs[0], 2)?;
+                                let params = SolanaTransactionParameters {
+                                    from: SolanaAddress(base.to_string()),
+                                    to: SolanaAddress(to.to_string()),
+                                    amount: lamports,
+                                    blockhash: blockhash.to_string(),
+                                    operation: Some(SolanaOperation::TransferWithSeed {
+                                        seed: from_seed,
+                                        owner: SolanaAddress(from_owner.to_string()),
+                                    }),
+                                    ..Default::default()
+                                };
+                                let mut tx = SolanaTransaction::parsed(&params)?;
+                                tx.signature = sig;
+                                Ok(tx)
+                            }
+                            SystemInstruction::Allocate { space } => {
+                                let account = instruction_account(&keys, &ixs[0], 0)?;
+                                let params = SolanaTransactionParameters {
+                                    from: SolanaAddress(account.to_string()),
+                                    to: SolanaAddress(account.to_string()),
+                                    amount: 0,
+                                    blockhash: blockhash.to_string(),
+                                    operation: Some(SolanaOperation::Allocate { space }),
+                                    ..Default::default()
+                                };
+                                let mut tx = SolanaTransaction::parsed(&params)?;
+                                tx.signature = sig;
+                                Ok(tx)
+                            }
+                            SystemInstruction::Assign { owner } => {
+                                let account = instruction_account(&keys, &ixs[0], 0)?;
+                                let params = SolanaTransactionParameters {
+                                    from: SolanaAddress(account.to_string()),
+                                    to: SolanaAddress(account.to_string()),
+                                    amount: 0,
+                                    blockhash: blockhash.to_string(),
+                                    operation: Some(SolanaOperation::Assign {
+                                        owner: SolanaAddress(owner.to_string()),
+                                    }),
+                                    ..Default::default()
+                                };
+                                let mut tx = SolanaTransaction::parsed(&params)?;
+                                tx.signature = sig;
+                                Ok(tx)
+                            }
+                            SystemInstruction::CreateAccountWithSeed {
+                                base,
+                                seed,
+                                lamports,
+                                space,
+                                owner,
+                            } => {
+                                let from = instruction_account(&keys, &ixs[0], 0)?;
+                                let to = instruction_account(&keys, &ixs[0], 1)?;
                                 let params = SolanaTransactionParameters {
-                                    token: Some(SolanaAddress(token.to_string())),
-                                    has_token_account: Some(true),
-                                    decimals: Some(decimals),
                                     from: SolanaAddress(from.to_string()),
-                                    to: SolanaAddress(dest.to_string()),
-                                    amount,
+                                    to: SolanaAddress(to.to_string()),
+                                    amount: lamports,
                                     blockhash: blockhash.to_string(),
+                                    operation: Some(SolanaOperation::CreateAccountWithSeed {
+                                        base: SolanaAddress(base.to_string()),
+                                        seed,
+                                        space,
+                                        owner: SolanaAddress(owner.to_string()),
+                                    }),
+                                    ..Default::default()
                                 };
-                                let mut tx = SolanaTransaction::new(&params)?;
+                                let mut tx = SolanaTransaction::parsed(&params)?;
                                 tx.signature = sig;
                                 Ok(tx)
                             }
                             _ => Err(TransactionError::Message(format!(
-                                "Unsupported token instruction: {:?}",
+                                "Unsupported system instruction: {:?}",
                                 ix
                             ))),
                         }
                     }
-                    _ => Err(TransactionError::Message(format!(
-                        "Unsupported program {}",
-                        program
-                    ))),
-                }
-            }
-            2 => {
-                let program1 = keys[ixs[0].program_id_index as usize];
-                let program2 = keys[ixs[1].program_id_index as usize];
-
-                if format!("{}", program1).as_str()
-                    != "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL"
-                {
-                    return Err(TransactionError::Message(format!(
-                        "Unsupported first program {}",
-                        program1
-                    )));
-                }
-
-                if format!("{}", program2).as_str() != "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"
-                {
-                    return Err(TransactionError::Message(format!(
-                        "Unsupported second program {}",
-                        program2
-                    )));
-                }
-
-                let account = &ixs[0].accounts;
-                let data = &ixs[1].data;
-
-                let funding_address = keys[account[0] as usize];
-                let funded_address = keys[account[2] as usize];
-                let token_address = keys[account[3] as usize];
-
-                let ix = TokenInstruction::unpack(data)
-                    .map_err(|e| TransactionError::Message(format!("{}", e)))?;
-
-                match ix {
-                    TokenInstruction::TransferChecked { amount, decimals } => {
+                    SPL_TOKEN_PROGRAM_ID | TOKEN_2022_PROGRAM_ID => {
+                        let account = |position| instruction_account(&keys, &ixs[0], position);
+                        let address = |key: Pubkey| SolanaAddress(key.to_string());
+                        // Checked transfers, with or without the Token-2022
+                        // fee, first. The other instructions share their
+                        // encoding between both programs.
+                        let (params, authority) = if let Ok((amount, decimals, transfer_fee)) =
+                            parse_transfer_checked(data)
+                        {
+                            let params = SolanaTransactionParameters {
+                                token: Some(address(account(1)?)),
+                                has_token_account: Some(true),
+                                decimals: Some(decimals),
+                                transfer_fee,
+                                from: address(account(3)?),
+                                to: address(account(2)?),
+                                amount,
+                                ..Default::default()
+                            };
+                            (params, 3)
+                        } else {
+                            let ix = TokenInstruction::unpack(data)
+                                .map_err(|e| TransactionError::Message(format!("{}", e)))?;
+                            match ix {
+                                // The legacy `Transfer` names neither the mint nor its
+                                // decimals, so it is kept as its own operation rather
+                                // than as a checked transfer of an unknown mint
+                                TokenInstruction::Transfer { amount } => {
+                                    let params = SolanaTransactionParameters {
+                                        from: address(account(2)?),
+                                        to: address(account(1)?),
+                                        amount,
+                                        operation: Some(SolanaOperation::UncheckedTokenTransfer {
+                                            source: address(account(0)?),
+                                        }),
+                                        ..Default::default()
+                                    };
+                                    (params, 2)
+                                }
+                                TokenInstruction::ApproveChecked { amount, decimals } => {
+                                    let token = account(1)?;
+                                    let owner = account(3)?;
+                                    // `Approve` delegates from the owner's associated
+                                    // token account, which is not kept in the params
+                                    let source = account(0)?;
+                                    if source
+                                        != get_associated_token_address_with_program_id(
+                                            &owner, &token, &program,
+                                        )
+                                    {
+                                        return Err(TransactionError::Message(format!(
+                                            "Approval from {} which is not the associated \
+                                             token account of {} is not supported",
+                                            source, owner
+                                        )));
+                                    }
+                                    let params = SolanaTransactionParameters {
+                                        token: Some(address(token)),
+                                        decimals: Some(decimals),
+                                        from: address(owner),
+                                        to: address(account(2)?),
+                                        amount,
+                                        operation: Some(SolanaOperation::Approve),
+                                        ..Default::default()
+                                    };
+                                    (params, 3)
+                                }
+                                TokenInstruction::MintToChecked { amount, decimals } => {
+                                    let params = SolanaTransactionParameters {
+                                        token: Some(address(account(0)?)),
+                                        decimals: Some(decimals),
+                                        from: address(account(2)?),
+                                        to: address(account(1)?),
+                                        amount,
+                                        operation: Some(SolanaOperation::MintTo),
+                                        ..Default::default()
+                                    };
+                                    (params, 2)
+                                }
+                                TokenInstruction::Revoke => {
+                                    let params = SolanaTransactionParameters {
+                                        from: address(account(1)?),
+                                        to: address(account(0)?),
+                                        operation: Some(SolanaOperation::Revoke),
+                                        ..Default::default()
+                                    };
+                                    (params, 1)
+                                }
+                                TokenInstruction::FreezeAccount | TokenInstruction::ThawAccount => {
+                                    let operation = match ix {
+                                        TokenInstruction::FreezeAccount => {
+                                            SolanaOperation::FreezeAccount
+                                        }
+                                        _ => SolanaOperation::ThawAccount,
+                                    };
+                                    let params = SolanaTransactionParameters {
+                                        token: Some(address(account(1)?)),
+                                        from: address(account(2)?),
+                                        to: address(account(0)?),
+                                        operation: Some(operation),
+                                        ..Default::default()
+                                    };
+                                    (params, 2)
+                                }
+                                TokenInstruction::SetAuthority {
+                                    authority_type,
+                                    new_authority,
+                                } => {
+                                    let new_authority: Option<Pubkey> = new_authority.into();
+                                    let params = SolanaTransactionParameters {
+                                        from: address(account(1)?),
+                                        to: address(account(0)?),
+                                        operation: Some(SolanaOperation::SetAuthority {
+                                            authority_type: authority_type.into(),
+                                            new_authority: new_authority.map(address),
+                                        }),
+                                        ..Default::default()
+                                    };
+                                    (params, 1)
+                                }
+                                _ => {
+                                    return Err(TransactionError::Message(format!(
+                                        "Unsupported token instruction: {:?}",
+                                        ix
+                                    )))
+                                }
+                            }
+                        };
                         let params = SolanaTransactionParameters {
-                            token: Some(SolanaAddress(token_address.to_string())),
-                            has_token_account: Some(false),
-                            decimals: Some(decimals),
-                            from: SolanaAddress(funding_address.to_string()),
-                            to: SolanaAddress(funded_address.to_string()),
-                            amount,
                             blockhash: blockhash.to_string(),
+                            token_program: (program == TOKEN_2022_PROGRAM_ID)
+                                .then(|| address(program)),
+                            multisig_signers: multisig_signers(&keys, &ixs[0], authority)?,
+                            ..params
                         };
-                        let mut tx = SolanaTransaction::new(&params)?;
+                        let mut tx = SolanaTransaction::parsed(&params)?;
+                        tx.signature = sig;
+                        Ok(tx)
+                    }
+                    STAKE_PROGRAM_ID => {
+                        let ix = bincode::deserialize::<StakeInstruction>(data)
+                            .map_err(SolanaError::from)?;
+                        let stake = instruction_account(&keys, &ixs[0], 0)?;
+                        let (authority, operation) = match ix {
+                            StakeInstruction::DelegateStake => {
+                                let vote_account = instruction_account(&keys, &ixs[0], 1)?;
+                                let operation = SolanaOperation::DelegateStake {
+                                    vote_account: SolanaAddress(vote_account.to_string()),
+                                };
+                                (instruction_account(&keys, &ixs[0], 5)?, operation)
+                            }
+                            StakeInstruction::Deactivate => (
+                                instruction_account(&keys, &ixs[0], 2)?,
+                                SolanaOperation::DeactivateStake,
+                            ),
+                            _ => {
+                                return Err(TransactionError::Message(format!(
+                                    "Unsupported stake instruction: {:?}",
+                                    ix
+                                )))
+                            }
+                        };
+                        let params = SolanaTransactionParameters {
+                            from: SolanaAddress(authority.to_string()),
+                            to: SolanaAddress(stake.to_string()),
+                            blockhash: blockhash.to_string(),
+                            operation: Some(operation),
+                            ..Default::default()
+                        };
+                        let mut tx = SolanaTransaction::parsed(&params)?;
                         tx.signature = sig;
                         Ok(tx)
                     }
                     _ => Err(TransactionError::Message(format!(
-                        "Unsupported token instruction: {:?}",
-                        ix
+                        "Unsupported program {}",
+                        program
                     ))),
                 }
             }
-            _ => Err(TransactionError::Message(format!(
-                "Unsupported instruction amount: {}",
-                ixs.len()
+            // SOL transfers of one payer, such as a chunk of a multi-transfer
+            n if n > 1 && ixs.iter().all(|ix| is_sol_transfer(&keys, ix)) => {
+                let params = parse_sol_multi_transfer(&keys, &ixs, &blockhash)?;
+                let mut tx = SolanaTransaction::parsed(&params)?;
+                tx.signature = sig;
+                Ok(tx)
+            }
+            2 => {
+                let program1 = program_id(&keys, &ixs[0])?;
+                let program2 = program_id(&keys, &ixs[1])?;
+
+                if program1 == SYSTEM_PROGRAM_ID && program2 == SYSTEM_PROGRAM_ID {
+                    let params = match bincode::deserialize::<SystemInstruction>(&ixs[1].data) {
+                        Ok(SystemInstruction::Transfer { .. }) => {
+                            parse_create_account(&keys, &ixs, &blockhash)?
+                        }
+                        _ => parse_create_nonce_account(&keys, &ixs, &blockhash)?,
+                    };
+                    let mut tx = SolanaTransaction::parsed(&params)?;
+                    tx.signature = sig;
+                    Ok(tx)
+                } else if program1 == SPL_TOKEN_PROGRAM_ID && program2 == SPL_TOKEN_PROGRAM_ID {
+                    let params = parse_token_batch(&keys, &ixs, &blockhash)?;
+                    let mut tx = SolanaTransaction::parsed(&params)?;
+                    tx.signature = sig;
+                    Ok(tx)
+                } else if program1 == SYSTEM_PROGRAM_ID && program2 == SPL_TOKEN_PROGRAM_ID {
+                    let params = parse_wrap_sol(&keys, &ixs, &blockhash)?;
+                    let mut tx = SolanaTransaction::parsed(&params)?;
+                    tx.signature = sig;
+                    Ok(tx)
+                } else {
+                    // Classify by program rather than position, some wallets
+                    // put the transfer before the account creation
+                    let (create, transfer, token_program) = match (program1, program2) {
+                        (
+                            ASSOCIATED_TOKEN_PROGRAM_ID,
+                            SPL_TOKEN_PROGRAM_ID | TOKEN_2022_PROGRAM_ID,
+                        ) => (&ixs[0], &ixs[1], program2),
+                        (
+                            SPL_TOKEN_PROGRAM_ID | TOKEN_2022_PROGRAM_ID,
+                            ASSOCIATED_TOKEN_PROGRAM_ID,
+                        ) => (&ixs[1], &ixs[0], program1),
+                        _ => {
+                            return Err(TransactionError::Message(format!(
+                                "Unsupported programs {} and {}",
+                                program1, program2
+                            )))
+                        }
+                    };
+
+                    // The funder of the associated token account may differ from
+                    // the authority of the token transfer in sponsored flows
+                    let data = &transfer.data;
+
+                    let funding_address = instruction_account(&keys, create, 0)?;
+                    let funded_address = instruction_account(&keys, create, 2)?;
+                    let token_address = instruction_account(&keys, create, 3)?;
+                    let authority = instruction_account(&keys, transfer, 3)?;
+                    let funder = if funding_address != authority {
+                        Some(SolanaAddress(funding_address.to_string()))
+                    } else {
+                        None
+                    };
+                    let token_program = if token_program == TOKEN_2022_PROGRAM_ID {
+                        Some(SolanaAddress(token_program.to_string()))
+                    } else {
+                        None
+                    };
+                    // An idempotent create is what a transfer built without
+                    // `has_token_account` emits
+                    let has_token_account = match create.data.as_slice() {
+                        [] | [0] => Some(false),
+                        [1] => None,
+                        data => {
+                            return Err(TransactionError::Message(format!(
+                                "Unsupported associated token account instruction: {:?}",
+                                data
+                            )))
+                        }
+                    };
+
+                    let (amount, decimals, transfer_fee) = parse_transfer_checked(data)?;
+                    let params = SolanaTransactionParameters {
+                        token: Some(SolanaAddress(token_address.to_string())),
+                        has_token_account,
+                        decimals: Some(decimals),
+                        from: SolanaAddress(authority.to_string()),
+                        to: SolanaAddress(funded_address.to_string()),
+                        amount,
+                        blockhash: blockhash.to_string(),
+                        funder,
+                        token_program,
+                        transfer_fee,
+                        multisig_signers: multisig_signers(&keys, transfer, 3)?,
+                        ..Default::default()
+                    };
+                    let mut tx = SolanaTransaction::parsed(&params)?;
+                    tx.signature = sig;
+                    Ok(tx)
+                }
+            }
+            0 => Err(TransactionError::Message(
+                "Unsupported instruction amount: 0".to_string(),
+            )),
+            _ => {
+                let params = parse_token_batch(&keys, &ixs, &blockhash)?;
+                let mut tx = SolanaTransaction::parsed(&params)?;
+                tx.signature = sig;
+                Ok(tx)
+            }
+        }?;
+        if let Some((nonce_account, nonce_authority)) = nonce {
+            tx.params.nonce_account = Some(SolanaAddress(nonce_account.to_string()));
+            if nonce_authority.to_string() != tx.params.from.0 {
+                tx.params.nonce_authority = Some(SolanaAddress(nonce_authority.to_string()));
+            }
+        }
+        tx.params.compute_unit_limit = compute_unit_limit;
+        tx.params.compute_unit_price = compute_unit_price;
+        tx.params.memo = memo;
+        let fee_payer = account_key(&keys, 0)?;
+        if fee_payer.to_string() != tx.params.from.0 {
+            tx.params.fee_payer = Some(SolanaAddress(fee_payer.to_string()));
+        }
+        if is_v0 {
+            tx.params.lookup_tables = Some(vec![]);
+        }
+        tx.unknown_instructions = unknown_instructions;
+        Ok(tx)
+    }
+}
+
+impl TryFrom<&[u8]> for SolanaTransaction {
+    type Error = TransactionError;
+
+    fn try_from(tx: &[u8]) -> Result<Self, Self::Error> {
+        SolanaTransaction::from_bytes(tx)
+    }
+}
+
+impl TryFrom<Vec<u8>> for SolanaTransaction {
+    type Error = TransactionError;
+
+    fn try_from(tx: Vec<u8>) -> Result<Self, Self::Error> {
+        SolanaTransaction::from_bytes(&tx)
+    }
+}
+
+impl TryFrom<Tx> for SolanaTransaction {
+    type Error = TransactionError;
+
+    fn try_from(tx: Tx) -> Result<Self, Self::Error> {
+        SolanaTransaction::try_from(VersionedTransaction::from(tx))
+    }
+}
+
+impl TryFrom<&SolanaTransaction> for Tx {
+    type Error = TransactionError;
+
+    /// Builds the legacy transaction with the attached signatures, leaving
+    /// the remaining signer slots zeroed. Fails if `lookup_tables` is set,
+    /// as the transaction then has a v0 message.
+    fn try_from(tx: &SolanaTransaction) -> Result<Self, Self::Error> {
+        if tx.params.lookup_tables.is_some() {
+            return Err(TransactionError::Message(
+                "A transaction with lookup tables has a v0 message".to_string(),
+            ));
+        }
+        let mut transaction = Tx::new_unsigned(tx.message()?);
+        if tx.signature.len() > transaction.signatures.len() {
+            return Err(TransactionError::Message(format!(
+                "{} signatures for {} signers",
+                tx.signature.len(),
+                transaction.signatures.len()
+            )));
+        }
+        for (slot, sig) in transaction
+            .signatures
+            .iter_mut()
+            .zip(to_signatures(&tx.signature)?)
+        {
+            *slot = sig;
+        }
+        Ok(transaction)
+    }
+}
+
+/// Returns the signers following the authority, the account at `authority`,
+/// of a token transfer spent by a multisig account
+fn multisig_signers(
+    keys: &[Pubkey],
+    ix: &CompiledInstruction,
+    authority: usize,
+) -> Result<Option<Vec<SolanaAddress>>, TransactionError> {
+    if ix.accounts.len() <= authority + 1 {
+        return Ok(None);
+    }
+    (authority + 1..ix.accounts.len())
+        .map(|position| {
+            Ok(SolanaAddress(
+                instruction_account(keys, ix, position)?.to_string(),
+            ))
+        })
+        .collect::<Result<_, _>>()
+        .map(Some)
+}
+
+/// Decodes the amount, decimals and, for the Token-2022
+/// `TransferCheckedWithFee`, the fee of a checked token transfer
+fn parse_transfer_checked(data: &[u8]) -> Result<(u64, u8, Option<u64>), TransactionError> {
+    // The tag of `TransferFeeExtension`, followed by the extension instruction
+    const TRANSFER_FEE_EXTENSION: u8 = 26;
+    match data.split_first() {
+        Some((&TRANSFER_FEE_EXTENSION, data)) => {
+            match TransferFeeInstruction::unpack(data).map_err(program_error)? {
+                TransferFeeInstruction::TransferCheckedWithFee {
+                    amount,
+                    decimals,
+                    fee,
+                } => Ok((amount, decimals, Some(fee))),
+                ix => Err(TransactionError::Message(format!(
+                    "Unsupported transfer fee instruction: {:?}",
+                    ix
+                ))),
+            }
+        }
+        _ => match TokenInstruction::unpack(data).map_err(program_error)? {
+            TokenInstruction::TransferChecked { amount, decimals } => Ok((amount, decimals, None)),
+            ix => Err(TransactionError::Message(format!(
+                "Unsupported token instruction: {:?}",
+                ix
             ))),
+        },
+    }
+}
+
+fn check_signature(signature: &[u8]) -> Result<(), TransactionError> {
+    if signature.len() != 64 {
+        return Err(TransactionError::Message(format!(
+            "Invalid signature length {}",
+            signature.len(),
+        )));
+    }
+    // An all-zero signature is a placeholder, e.g. from a signer that
+    // failed silently, and can never verify
+    if signature.iter().all(|b| *b == 0) {
+        return Err(TransactionError::Message(
+            "Signature is all zeros".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Decodes the system and token instructions this crate builds into
+/// explicit fields for `approval_json`. The fields are spelled out rather
+/// than taken from the `Debug` output of the SDK types, which may change
+/// between versions and would invalidate recorded approvals.
+fn approval_decoded(program: &Pubkey, data: &[u8]) -> Option<serde_json::Value> {
+    if *program == SYSTEM_PROGRAM_ID {
+        let decoded = match bincode::deserialize::<SystemInstruction>(data).ok()? {
+            SystemInstruction::Transfer { lamports } => {
+                json!({ "instruction": "Transfer", "lamports": lamports })
+            }
+            SystemInstruction::TransferWithSeed {
+                lamports,
+                from_seed,
+                from_owner,
+            } => json!({
+                "instruction": "TransferWithSeed",
+                "lamports": lamports,
+                "seed": from_seed,
+                "owner": from_owner.to_string(),
+            }),
+            SystemInstruction::CreateAccount {
+                lamports,
+                space,
+                owner,
+            } => json!({
+                "instruction": "CreateAccount",
+                "lamports": lamports,
+                "space": space,
+                "owner": owner.to_string(),
+            }),
+            SystemInstruction::CreateAccountWithSeed {
+                base,
+                seed,
+                lamports,
+                space,
+                owner,
+            } => json!({
+                "instruction": "CreateAccountWithSeed",
+                "base": base.to_string(),
+                "seed": seed,
+                "lamports": lamports,
+                "space": space,
+                "owner": owner.to_string(),
+            }),
+            SystemInstruction::Allocate { space } => {
+                json!({ "instruction": "Allocate", "space": space })
+            }
+            SystemInstruction::Assign { owner } => {
+                json!({ "instruction": "Assign", "owner": owner.to_string() })
+            }
+            SystemInstruction::AdvanceNonceAccount => {
+                json!({ "instruction": "AdvanceNonceAccount" })
+            }
+            SystemInstruction::InitializeNonceAccount(authority) => json!({
+                "instruction": "InitializeNonceAccount",
+                "authority": authority.to_string(),
+            }),
+            _ => return None,
+        };
+        return Some(decoded);
+    }
+    if *program != SPL_TOKEN_PROGRAM_ID && *program != TOKEN_2022_PROGRAM_ID {
+        return None;
+    }
+    let decoded = match TokenInstruction::unpack(data).ok()? {
+        TokenInstruction::Transfer { amount } => {
+            json!({ "instruction": "Transfer", "amount": amount })
         }
+        TokenInstruction::TransferChecked { amount, decimals } => json!({
+            "instruction": "TransferChecked",
+            "amount": amount,
+            "decimals": decimals,
+        }),
+        TokenInstruction::ApproveChecked { amount, decimals } => json!({
+            "instruction": "ApproveChecked",
+            "amount": amount,
+            "decimals": decimals,
+        }),
+        TokenInstruction::MintToChecked { amount, decimals } => json!({
+            "instruction": "MintToChecked",
+            "amount": amount,
+            "decimals": decimals,
+        }),
+        TokenInstruction::Revoke => json!({ "instruction": "Revoke" }),
+        TokenInstruction::SyncNative => json!({ "instruction": "SyncNative" }),
+        TokenInstruction::FreezeAccount => json!({ "instruction": "FreezeAccount" }),
+        TokenInstruction::ThawAccount => json!({ "instruction": "ThawAccount" }),
+        TokenInstruction::SetAuthority {
+            authority_type,
+            new_authority,
+        } => json!({
+            "instruction": "SetAuthority",
+            "authority_type": SolanaAuthorityType::from(authority_type),
+            "new_authority": Option::<Pubkey>::from(new_authority).map(|key| key.to_string()),
+        }),
+        _ => return None,
+    };
+    Some(decoded)
+}
+
+/// Lays out `signatures` in the order of `signers`, the first
+/// `num_required_signatures` account keys of the message, as the runtime
+/// matches them by position
+fn order_signatures(
+    signers: &[SolanaAddress],
+    signatures: &HashMap<SolanaAddress, Vec<u8>>,
+) -> Vec<Vec<u8>> {
+    signers
+        .iter()
+        .map(|signer| match signatures.get(signer) {
+            Some(signature) => signature.clone(),
+            None => vec![0u8; 64],
+        })
+        .collect()
+}
+
+fn to_signatures(signatures: &[Vec<u8>]) -> Result<Vec<Signature>, TransactionError> {
+    signatures
+        .iter()
+        .map(|sig| {
+            Signature::try_from(sig.as_slice())
+                .map_err(|e| TransactionError::Message(format!("{}", e)))
+        })
+        .collect()
+}
+
+fn total_amount(recipients: &[SolanaRecipient]) -> Result<u64, TransactionError> {
+    recipients.iter().try_fold(0u64, |total, recipient| {
+        total
+            .checked_add(recipient.amount)
+            .ok_or_else(|| TransactionError::Message("Total amount overflows u64".to_string()))
+    })
+}
+
+fn parse_wrap_sol(
+    keys: &[Pubkey],
+    ixs: &[CompiledInstruction],
+    blockhash: &Hash,
+) -> Result<SolanaTransactionParameters, TransactionError> {
+    let transfer = bincode::deserialize::<SystemInstruction>(&ixs[0].data)
+        .map_err(|e| TransactionError::Message(format!("{}", e)))?;
+    let sync = TokenInstruction::unpack(&ixs[1].data).map_err(program_error)?;
+    match (transfer, sync) {
+        (SystemInstruction::Transfer { lamports }, TokenInstruction::SyncNative) => {
+            let from = instruction_account(keys, &ixs[0], 0)?;
+            let account = instruction_account(keys, &ixs[0], 1)?;
+            if instruction_account(keys, &ixs[1], 0)? != account {
+                return Err(TransactionError::Message(
+                    "SyncNative does not target the funded account".to_string(),
+                ));
+            }
+            Ok(SolanaTransactionParameters {
+                from: SolanaAddress(from.to_string()),
+                to: SolanaAddress(account.to_string()),
+                amount: lamports,
+                blockhash: blockhash.to_string(),
+                operation: Some(SolanaOperation::WrapSol),
+                ..Default::default()
+            })
+        }
+        (transfer, sync) => Err(TransactionError::Message(format!(
+            "Unsupported instructions: {:?}, {:?}",
+            transfer, sync
+        ))),
     }
+}
 
-    fn to_transaction_id(&self) -> Result<Self::TransactionId, TransactionError> {
-        match &self.signature {
-            Some(sig) => {
-                let mut txid = [0u8; 64];
-                txid.copy_from_slice(sig);
-                Ok(SolanaTransactionId(txid))
+/// Parses a run of `TransferChecked` instructions sharing one authority,
+/// each spending from the authority's associated token account
+fn parse_token_batch(
+    keys: &[Pubkey],
+    ixs: &[CompiledInstruction],
+    blockhash: &Hash,
+) -> Result<SolanaTransactionParameters, TransactionError> {
+    let mut from = None;
+    let mut transfers = vec![];
+    for ix in ixs {
+        let program = program_id(keys, ix)?;
+        if program != SPL_TOKEN_PROGRAM_ID {
+            return Err(TransactionError::Message(format!(
+                "Unsupported program {}",
+                program
+            )));
+        }
+        let (amount, decimals) = match TokenInstruction::unpack(&ix.data).map_err(program_error)? {
+            TokenInstruction::TransferChecked { amount, decimals } => (amount, decimals),
+            ix => {
+                return Err(TransactionError::Message(format!(
+                    "Unsupported token instruction: {:?}",
+                    ix
+                )))
             }
-            None => Err(TransactionError::Message(
-                "Transaction is not signed".to_string(),
-            )),
+        };
+        let source = instruction_account(keys, ix, 0)?;
+        let token = instruction_account(keys, ix, 1)?;
+        let dest = instruction_account(keys, ix, 2)?;
+        let authority = instruction_account(keys, ix, 3)?;
+        if *from.get_or_insert(authority) != authority {
+            return Err(TransactionError::Message(
+                "Token transfers have different authorities".to_string(),
+            ));
+        }
+        if source != get_associated_token_address(&authority, &token) {
+            return Err(TransactionError::Message(format!(
+                "Source {} is not the associated token account of {}",
+                source, authority
+            )));
         }
+        transfers.push(SolanaTokenTransfer {
+            token: SolanaAddress(token.to_string()),
+            to: SolanaAddress(dest.to_string()),
+            amount,
+            decimals,
+        });
     }
+    let from = SolanaAddress(from.unwrap_or_default().to_string());
+    Ok(SolanaTransactionParameters {
+        from: from.clone(),
+        to: from,
+        blockhash: blockhash.to_string(),
+        operation: Some(SolanaOperation::TokenBatch { transfers }),
+        ..Default::default()
+    })
 }
 
-#[test]
-fn test() {
-    let tx = "BU8oN58NjvzGdbuQ8zGKF9cJ7N25iWRRgnLodf42gEVDnzcQ3g5y7eygBviCRQHH4sC335gt575JA2NfjpX3P7m1vZ5WYWxHem7wW3Pc4S6YYi4ftivYiGqTMr6eKtUVCbBZabwyMuZ7iGjUtTB6L7LnfQj6wGduNUqwpGPy2xD8aFps6zRfgwNAXe9tpoa3tQvTnyU8WgkpiZjkBFdfXFw8abhsUZLZsxaYra2CHmqrXwG6VFUfhTdYANPTXcBcZ2a75RmqC19d5rYJPexmpGJV529A4WXgE4Pm5Gk5AUB7LcNmAxfkKxJk3ikGohb9n3B7vJ3T9zJZg4i6xEGapobavsLwMuYkCjnRBQ69rouMCJEtz33XNuwx1ZN84cGimZV1KSbwQgcPDFzgdZR2ZisViDWAJUXkadfCfADNEME1jxmHDy7oX9gTYJvkeZAnoFjxVhKrVZft8FaADcRgNcdZJPdt9rMMSpCJXBFgBVsGaqo6iteJqg79qQrEoScRviUh6scB7iwCh";
-    let tx = SolanaTransaction::from_str(tx).unwrap();
-    let txid = tx.to_transaction_id().unwrap();
-    println!("{}", txid);
+fn is_sol_transfer(keys: &[Pubkey], ix: &CompiledInstruction) -> bool {
+    program_id(keys, ix).ok() == Some(SYSTEM_PROGRAM_ID)
+        && matches!(
+            bincode::deserialize::<SystemInstruction>(&ix.data),
+            Ok(SystemInstruction::Transfer { .. })
+        )
+}
+
+fn parse_sol_multi_transfer(
+    keys: &[Pubkey],
+    ixs: &[CompiledInstruction],
+    blockhash: &Hash,
+) -> Result<SolanaTransactionParameters, TransactionError> {
+    let mut from = None;
+    let mut recipients = vec![];
+    for ix in ixs {
+        let lamports = match bincode::deserialize::<SystemInstruction>(&ix.data) {
+            Ok(SystemInstruction::Transfer { lamports }) => lamports,
+            ix => {
+                return Err(TransactionError::Message(format!(
+                    "Unsupported system instruction: {:?}",
+                    ix
+                )))
+            }
+        };
+        let source = instruction_account(keys, ix, 0)?;
+        if *from.get_or_insert(source) != source {
+            return Err(TransactionError::Message(
+                "SOL transfers have different sources".to_string(),
+            ));
+        }
+        recipients.push(SolanaRecipient {
+            to: SolanaAddress(instruction_account(keys, ix, 1)?.to_string()),
+            amount: lamports,
+        });
+    }
+    let from = SolanaAddress(from.unwrap_or_default().to_string());
+    Ok(SolanaTransactionParameters {
+        from: from.clone(),
+        to: from,
+        blockhash: blockhash.to_string(),
+        operation: Some(SolanaOperation::MultiTransfer { recipients }),
+        ..Default::default()
+    })
+}
+
+fn program_error(error: ProgramError) -> TransactionError {
+    SolanaError::from(error).into()
+}
+
+fn parse_create_account(
+    keys: &[Pubkey],
+    ixs: &[CompiledInstruction],
+    blockhash: &Hash,
+) -> Result<SolanaTransactionParameters, TransactionError> {
+    let ix_create = bincode::deserialize::<SystemInstruction>(&ixs[0].data)
+        .map_err(|e| TransactionError::Message(format!("{}", e)))?;
+    let ix_transfer = bincode::deserialize::<SystemInstruction>(&ixs[1].data)
+        .map_err(|e| TransactionError::Message(format!("{}", e)))?;
+
+    match (ix_create, ix_transfer) {
+        (
+            SystemInstruction::CreateAccount {
+                lamports,
+                space,
+                owner,
+            },
+            SystemInstruction::Transfer { lamports: amount },
+        ) => {
+            let from = instruction_account(keys, &ixs[0], 0)?;
+            let to = instruction_account(keys, &ixs[0], 1)?;
+            if instruction_account(keys, &ixs[1], 0)? != from
+                || instruction_account(keys, &ixs[1], 1)? != to
+            {
+                return Err(TransactionError::Message(
+                    "Transfer does not fund the created account".to_string(),
+                ));
+            }
+            Ok(SolanaTransactionParameters {
+                from: SolanaAddress(from.to_string()),
+                to: SolanaAddress(to.to_string()),
+                amount,
+                blockhash: blockhash.to_string(),
+                operation: Some(SolanaOperation::CreateAccount {
+                    lamports,
+                    space,
+                    owner: SolanaAddress(owner.to_string()),
+                }),
+                ..Default::default()
+            })
+        }
+        (ix_create, ix_transfer) => Err(TransactionError::Message(format!(
+            "Unsupported system instructions: {:?}, {:?}",
+            ix_create, ix_transfer
+        ))),
+    }
+}
+
+fn parse_create_nonce_account(
+    keys: &[Pubkey],
+    ixs: &[CompiledInstruction],
+    blockhash: &Hash,
+) -> Result<SolanaTransactionParameters, TransactionError> {
+    let ix_create = bincode::deserialize::<SystemInstruction>(&ixs[0].data)
+        .map_err(|e| TransactionError::Message(format!("{}", e)))?;
+    let ix_initialize = bincode::deserialize::<SystemInstruction>(&ixs[1].data)
+        .map_err(|e| TransactionError::Message(format!("{}", e)))?;
+
+    match (ix_create, ix_initialize) {
+        (
+            SystemInstruction::CreateAccount { lamports, .. },
+            SystemInstruction::InitializeNonceAccount(authority),
+        ) => {
+            let from = instruction_account(keys, &ixs[0], 0)?;
+            let nonce_account = instruction_account(keys, &ixs[0], 1)?;
+            Ok(SolanaTransactionParameters::create_nonce_account(
+                SolanaAddress(from.to_string()),
+                SolanaAddress(nonce_account.to_string()),
+                SolanaAddress(authority.to_string()),
+                lamports,
+                blockhash.to_string(),
+            ))
+        }
+        (ix_create, ix_initialize) => Err(TransactionError::Message(format!(
+            "Unsupported system instructions: {:?}, {:?}",
+            ix_create, ix_initialize
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{
+        instruction::AccountMeta,
+        signature::{Keypair, Signer},
+    };
+
+    const ALICE: &str = "8tR45MbTcEq1W4dMXnwe7KW7xqykNxnyoBQoASMtqHK";
+    const BOB: &str = "DPCG5xKuxK3NCL8FTn8h2vFharp9MbYmUZeSA2eLx4RG";
+    const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+    const BLOCKHASH: &str = "9zb7Lrb2hVgDz5oaQjTvT6gEhVnLg3wUTmxFdJeBxwzS";
+
+    fn token_params(has_token_account: bool) -> SolanaTransactionParameters {
+        SolanaTransactionParameters::token(
+            SolanaAddress(USDC.to_string()),
+            has_token_account,
+            SolanaAddress(ALICE.to_string()),
+            SolanaAddress(BOB.to_string()),
+            1_000_000,
+            6,
+            BLOCKHASH.to_string(),
+        )
+    }
+
+    const TX: &str = "BU8oN58NjvzGdbuQ8zGKF9cJ7N25iWRRgnLodf42gEVDnzcQ3g5y7eygBviCRQHH4sC335gt575JA2NfjpX3P7m1vZ5WYWxHem7wW3Pc4S6YYi4ftivYiGqTMr6eKtUVCbBZabwyMuZ7iGjUtTB6L7LnfQj6wGduNUqwpGPy2xD8aFps6zRfgwNAXe9tpoa3tQvTnyU8WgkpiZjkBFdfXFw8abhsUZLZsxaYra2CHmqrXwG6VFUfhTdYANPTXcBcZ2a75RmqC19d5rYJPexmpGJV529A4WXgE4Pm5Gk5AUB7LcNmAxfkKxJk3ikGohb9n3B7vJ3T9zJZg4i6xEGapobavsLwMuYkCjnRBQ69rouMCJEtz33XNuwx1ZN84cGimZV1KSbwQgcPDFzgdZR2ZisViDWAJUXkadfCfADNEME1jxmHDy7oX9gTYJvkeZAnoFjxVhKrVZft8FaADcRgNcdZJPdt9rMMSpCJXBFgBVsGaqo6iteJqg79qQrEoScRviUh6scB7iwCh";
+
+    #[test]
+    fn test() {
+        let tx = SolanaTransaction::from_str(TX).unwrap();
+        let txid = tx.to_transaction_id().unwrap();
+        println!("{}", txid);
+    }
+
+    #[test]
+    fn test_base64() {
+        let tx = SolanaTransaction::from_str(TX).unwrap();
+        let bytes = bs58::decode(TX).into_vec().unwrap();
+        let tx_base64 = BASE64_STANDARD.encode(bytes);
+
+        assert_eq!(SolanaTransaction::from_base64(&tx_base64).unwrap(), tx);
+        assert_eq!(tx.to_base64().unwrap(), tx_base64);
+    }
+
+    #[test]
+    fn test_base58() {
+        let tx = SolanaTransaction::from_str(TX).unwrap();
+        assert_eq!(tx.to_base58().unwrap(), TX);
+        assert_eq!(tx.to_string(), TX);
+        assert_eq!(SolanaTransaction::from_str(&tx.to_string()).unwrap(), tx);
+    }
+
+    #[test]
+    fn test_explorer_url() {
+        let txid = SolanaTransaction::from_str(TX)
+            .unwrap()
+            .to_transaction_id()
+            .unwrap();
+        assert_eq!(
+            txid.explorer_url(SolanaCluster::Mainnet),
+            format!("https://explorer.solana.com/tx/{}", txid)
+        );
+        assert_eq!(
+            txid.explorer_url(SolanaCluster::Testnet),
+            format!("https://explorer.solana.com/tx/{}?cluster=testnet", txid)
+        );
+        assert_eq!(
+            txid.explorer_url(SolanaCluster::Devnet),
+            format!("https://explorer.solana.com/tx/{}?cluster=devnet", txid)
+        );
+        assert_eq!(
+            txid.explorer_url(SolanaCluster::Custom("http://localhost:8899".to_string())),
+            format!(
+                "https://explorer.solana.com/tx/{}?cluster=custom&customUrl=http%3A%2F%2Flocalhost%3A8899",
+                txid
+            )
+        );
+    }
+
+    #[test]
+    fn test_cluster_rpc_url() {
+        assert_eq!(
+            SolanaCluster::Mainnet.rpc_url(),
+            "https://api.mainnet-beta.solana.com"
+        );
+        assert_eq!(
+            SolanaCluster::Testnet.rpc_url(),
+            "https://api.testnet.solana.com"
+        );
+        assert_eq!(
+            SolanaCluster::Devnet.rpc_url(),
+            "https://api.devnet.solana.com"
+        );
+        assert_eq!(
+            SolanaCluster::Custom("http://localhost:8899".to_string()).rpc_url(),
+            "http://localhost:8899"
+        );
+    }
+
+    #[test]
+    fn test_transaction_id_conversions() {
+        let txid = SolanaTransaction::from_str(TX)
+            .unwrap()
+            .to_transaction_id()
+            .unwrap();
+        assert_eq!(
+            SolanaTransactionId::from_str(&txid.to_string()).unwrap(),
+            txid
+        );
+        assert_eq!(SolanaTransactionId::from(txid.0), txid);
+        assert_eq!(txid.as_ref(), &txid.0[..]);
+
+        let mut tx = SolanaTransaction::new(&token_params(true)).unwrap();
+        tx.sign(vec![3u8; 64], 0).unwrap();
+        let txid = tx.to_transaction_id().unwrap();
+        assert_eq!(txid.bytes(), &[3u8; 64]);
+        assert_eq!(txid.to_vec(), vec![3u8; 64]);
+
+        assert!(SolanaTransactionId::from_str("0OIl").is_err());
+        assert!(SolanaTransactionId::from_str(BLOCKHASH).is_err());
+    }
+
+    #[test]
+    fn test_refresh_blockhash() {
+        let mut tx = SolanaTransaction::new(&token_params(true)).unwrap();
+        let fresh = Hash::new_unique();
+
+        let bytes = tx.to_bytes_with_blockhash(&fresh.to_string()).unwrap();
+        let msg = bincode::deserialize::<Message>(&bytes).unwrap();
+        assert_eq!(msg.recent_blockhash, fresh);
+        assert_eq!(tx.params.blockhash, BLOCKHASH);
+        assert!(tx.to_bytes_with_blockhash("invalid").is_err());
+
+        tx.sign(vec![1u8; 64], 0).unwrap();
+        tx.set_blockhash(fresh.to_string());
+        assert!(!tx.is_signed());
+        assert_eq!(tx.message().unwrap().recent_blockhash, fresh);
+    }
+
+    #[test]
+    fn test_blockhash_expiry() {
+        let mut params = token_params(true);
+        assert_eq!(params.recent_blockhash().is_expired(1000), None);
+
+        params.last_valid_block_height = Some(1000);
+        let blockhash = params.recent_blockhash();
+        assert_eq!(blockhash.hash, BLOCKHASH);
+        assert_eq!(blockhash.is_expired(999), Some(false));
+        assert_eq!(blockhash.is_expired(1000), Some(false));
+        assert_eq!(blockhash.is_expired(1001), Some(true));
+
+        let mut tx = SolanaTransaction::new(&params).unwrap();
+        assert_eq!(tx.to_bytes_at_height(1000).unwrap(), tx.to_bytes().unwrap());
+        assert!(tx.to_bytes_at_height(1001).is_err());
+
+        tx.set_blockhash(Hash::new_unique().to_string());
+        assert!(tx.to_bytes_at_height(1001).is_ok());
+    }
+
+    #[test]
+    fn test_verify_token_decimals() {
+        let tx = SolanaTransaction::from_str(TX).unwrap();
+        assert!(tx.verify_token_decimals(6).is_ok());
+        assert!(tx
+            .verify_token_decimals(9)
+            .unwrap_err()
+            .to_string()
+            .contains("mismatch"));
+    }
+
+    #[test]
+    fn test_constructors() {
+        let params = SolanaTransactionParameters::native(
+            SolanaAddress(ALICE.to_string()),
+            SolanaAddress(BOB.to_string()),
+            1_000_000,
+            BLOCKHASH.to_string(),
+        );
+        assert_eq!(params.token, None);
+        assert_eq!(params.has_token_account, None);
+        let msg = SolanaTransaction::new(&params).unwrap().message().unwrap();
+        assert_eq!(msg.instructions.len(), 1);
+        assert_eq!(
+            msg.account_keys[msg.instructions[0].program_id_index as usize],
+            SYSTEM_PROGRAM_ID
+        );
+
+        let params = token_params(false);
+        assert_eq!(params.token, Some(SolanaAddress(USDC.to_string())));
+        assert_eq!(params.has_token_account, Some(false));
+        assert_eq!(params.decimals, Some(6));
+        assert_eq!(params.operation, None);
+        let msg = SolanaTransaction::new(&params).unwrap().message().unwrap();
+        assert_eq!(msg.instructions.len(), 2);
+    }
+
+    #[test]
+    fn test_total_debit() {
+        let mut params = SolanaTransactionParameters::native(
+            SolanaAddress(ALICE.to_string()),
+            SolanaAddress(BOB.to_string()),
+            1_000_000,
+            BLOCKHASH.to_string(),
+        );
+        let tx = SolanaTransaction::new(&params).unwrap();
+        assert_eq!(tx.total_debit(5000, 2_039_280).unwrap(), 1_005_000);
+
+        params.compute_unit_limit = Some(300);
+        params.compute_unit_price = Some(10_000);
+        let tx = SolanaTransaction::new(&params).unwrap();
+        assert_eq!(tx.total_debit(5000, 2_039_280).unwrap(), 1_005_003);
+
+        let tx = SolanaTransaction::new(&token_params(true)).unwrap();
+        assert_eq!(tx.total_debit(5000, 2_039_280).unwrap(), 5000);
+
+        let tx = SolanaTransaction::new(&token_params(false)).unwrap();
+        assert_eq!(tx.total_debit(5000, 2_039_280).unwrap(), 2_044_280);
+
+        let mut params = token_params(false);
+        params.funder = Some(SolanaAddress(BOB.to_string()));
+        params.fee_payer = Some(SolanaAddress(BOB.to_string()));
+        let tx = SolanaTransaction::new(&params).unwrap();
+        assert_eq!(tx.total_debit(5000, 2_039_280).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_max_transferable() {
+        assert_eq!(max_transferable(1_000_000, 1, 0), 995_000);
+        assert_eq!(max_transferable(1_000_000, 2, 890_880), 99_120);
+        assert_eq!(max_transferable(890_880, 1, 890_880), 0);
+        assert_eq!(max_transferable(500_000, 1, 890_880), 0);
+        assert_eq!(max_transferable(4_999, 1, 0), 0);
+        assert_eq!(max_transferable(u64::MAX, u64::MAX, 0), 0);
+
+        let params = SolanaTransactionParameters::sweep(
+            SolanaAddress(ALICE.to_string()),
+            SolanaAddress(BOB.to_string()),
+            1_000_000,
+            0,
+            BLOCKHASH.to_string(),
+        );
+        assert_eq!(params.amount, 995_000);
+        assert!(SolanaTransaction::new(&params).is_ok());
+
+        let params = SolanaTransactionParameters::sweep(
+            SolanaAddress(ALICE.to_string()),
+            SolanaAddress(BOB.to_string()),
+            500_000,
+            890_880,
+            BLOCKHASH.to_string(),
+        );
+        assert_eq!(params.amount, 0);
+        assert!(SolanaTransaction::new(&params).is_err());
+    }
+
+    #[test]
+    fn test_new_token_transfer_with() {
+        let mut params = token_params(true);
+        params.has_token_account = None;
+
+        let tx = SolanaTransaction::new_token_transfer_with(&params, |_| true).unwrap();
+        assert_eq!(tx.message().unwrap().instructions.len(), 1);
+
+        let tx = SolanaTransaction::new_token_transfer_with(&params, |ata| {
+            let bob = Pubkey::from_str(BOB).unwrap();
+            let usdc = Pubkey::from_str(USDC).unwrap();
+            assert_eq!(ata.0, get_associated_token_address(&bob, &usdc).to_string());
+            false
+        })
+        .unwrap();
+        assert_eq!(tx.message().unwrap().instructions.len(), 2);
+    }
+
+    #[test]
+    fn test_params_string_form() {
+        let mut params = token_params(false);
+        params.funder = Some(SolanaAddress(BOB.to_string()));
+
+        let s = params.to_string();
+        assert_eq!(SolanaTransactionParameters::from_str(&s).unwrap(), params);
+        assert_eq!(s, params.to_string());
+        assert!(SolanaTransactionParameters::from_str("{}").is_err());
+    }
+
+    #[test]
+    fn test_is_signed() {
+        let mut tx = SolanaTransaction::new(&token_params(true)).unwrap();
+        assert!(!tx.is_signed());
+        assert_eq!(tx.signature_base58(), None);
+
+        tx.sign(vec![1u8; 64], 0).unwrap();
+        assert!(tx.is_signed());
+        assert_eq!(
+            tx.signature_base58(),
+            Some(tx.to_transaction_id().unwrap().to_string())
+        );
+    }
+
+    #[test]
+    fn test_same_params() {
+        let tx = SolanaTransaction::new(&token_params(true)).unwrap();
+        let signed = tx.with_signature(vec![1u8; 64]).unwrap();
+        assert!(tx.same_params(&signed));
+        assert_ne!(tx, signed);
+
+        let other = SolanaTransaction::new(&token_params(false)).unwrap();
+        assert!(!tx.same_params(&other));
+    }
+
+    #[test]
+    fn test_clone_unsigned() {
+        let mut tx = SolanaTransaction::new(&token_params(true)).unwrap();
+        let unsigned_bytes = tx.to_bytes().unwrap();
+        tx.sign(vec![1u8; 64], 0).unwrap();
+
+        let unsigned = tx.clone_unsigned();
+        assert!(!unsigned.is_signed());
+        assert_eq!(unsigned.params, tx.params);
+        assert_eq!(unsigned.to_bytes().unwrap(), unsigned_bytes);
+        assert_eq!(
+            unsigned.to_bytes().unwrap(),
+            tx.versioned_message().unwrap().serialize()
+        );
+
+        assert_eq!(unsigned.with_signature(vec![1u8; 64]).unwrap(), tx);
+        assert!(unsigned.with_signature(vec![1u8; 63]).is_err());
+    }
+
+    #[test]
+    fn test_sdk_transaction_conversion() {
+        let params = SolanaTransactionParameters::native(
+            SolanaAddress(ALICE.to_string()),
+            SolanaAddress(BOB.to_string()),
+            1_000_000,
+            BLOCKHASH.to_string(),
+        );
+        let mut tx = SolanaTransaction::new(&params).unwrap();
+
+        let unsigned = Tx::try_from(&tx).unwrap();
+        assert_eq!(unsigned.message, tx.message().unwrap());
+        assert_eq!(
+            SolanaTransaction::try_from(unsigned).unwrap().params,
+            params
+        );
+
+        tx.sign(vec![1u8; 64], 0).unwrap();
+        let signed = Tx::try_from(&tx).unwrap();
+        assert_eq!(signed.signatures, vec![Signature::from([1u8; 64])]);
+        assert_eq!(bincode::serialize(&signed).unwrap(), tx.to_bytes().unwrap());
+        assert_eq!(SolanaTransaction::try_from(signed).unwrap(), tx);
+    }
+
+    #[test]
+    fn test_sign_rejects_placeholder() {
+        let mut tx = SolanaTransaction::new(&token_params(true)).unwrap();
+        assert!(tx.sign(vec![0u8; 64], 0).is_err());
+        assert!(tx.sign(vec![1u8; 63], 0).is_err());
+        assert!(!tx.is_signed());
+
+        let keypair = Keypair::new();
+        let mut params = token_params(true);
+        params.from = SolanaAddress(keypair.pubkey().to_string());
+        let mut tx = SolanaTransaction::new(&params).unwrap();
+        let sig = keypair.sign_message(&tx.to_bytes().unwrap());
+        tx.sign(sig.as_ref().to_vec(), 0).unwrap();
+        assert!(Tx::try_from(&tx).unwrap().verify().is_ok());
+    }
+
+    #[test]
+    fn test_verify() {
+        let keypair = Keypair::new();
+        let mut params = token_params(true);
+        params.from = SolanaAddress(keypair.pubkey().to_string());
+        let mut tx = SolanaTransaction::new(&params).unwrap();
+        assert!(tx.verify().is_err());
+
+        let msg = tx.to_bytes().unwrap();
+        tx.sign(keypair.sign_message(&msg).as_ref().to_vec(), 0)
+            .unwrap();
+        assert!(tx.verify().is_ok());
+
+        let other = Keypair::new();
+        tx.sign(other.sign_message(&msg).as_ref().to_vec(), 0)
+            .unwrap();
+        assert!(tx.verify().is_err());
+    }
+
+    #[test]
+    fn test_try_from_bytes() {
+        let mut tx = SolanaTransaction::new(&token_params(false)).unwrap();
+        let bytes = tx.sign(vec![1u8; 64], 0).unwrap();
+
+        let parsed = SolanaTransaction::from_bytes(&bytes).unwrap();
+        assert_eq!(
+            SolanaTransaction::try_from(bytes.as_slice()).unwrap(),
+            parsed
+        );
+        assert_eq!(SolanaTransaction::try_from(bytes).unwrap(), parsed);
+        assert!(SolanaTransaction::try_from(vec![0u8; 3]).is_err());
+    }
+
+    #[test]
+    fn test_signature_json() {
+        let mut tx = SolanaTransaction::new(&token_params(true)).unwrap();
+        let json = serde_json::to_value(&tx).unwrap();
+        assert_eq!(json["signature"], json!([]));
+        assert_eq!(
+            serde_json::from_value::<SolanaTransaction>(json).unwrap(),
+            tx
+        );
+
+        tx.sign(vec![1u8; 64], 0).unwrap();
+        let json = serde_json::to_string(&tx).unwrap();
+        let value = serde_json::from_str::<serde_json::Value>(&json).unwrap();
+        assert_eq!(
+            value["signature"],
+            json!([tx.to_transaction_id().unwrap().to_string()])
+        );
+        assert_eq!(
+            serde_json::from_str::<SolanaTransaction>(&json).unwrap(),
+            tx
+        );
+    }
+
+    #[test]
+    fn test_required_signers() {
+        let tx = SolanaTransaction::new(&token_params(false)).unwrap();
+        let msg = tx.message().unwrap();
+        assert_eq!(msg.instructions.len(), 2);
+        let signers = tx.required_signers().unwrap();
+        assert_eq!(signers, vec![SolanaAddress(ALICE.to_string())]);
+        // The fee payer is always the first required signer
+        assert_eq!(signers[0].0, msg.account_keys[0].to_string());
+    }
+
+    #[test]
+    fn test_instruction_summaries() {
+        let tx = SolanaTransaction::new(&token_params(false)).unwrap();
+        assert_eq!(
+            tx.instruction_summaries().unwrap(),
+            vec![
+                SolanaInstructionSummary {
+                    program: SolanaAddress::associated_token_program(),
+                    account_count: 6,
+                    data_len: 1,
+                },
+                SolanaInstructionSummary {
+                    program: SolanaAddress::token_program(),
+                    account_count: 4,
+                    data_len: 10,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decoded_accounts() {
+        let params = token_params(false);
+        let tx = SolanaTransaction::new(&params).unwrap();
+        let accounts = tx.decoded_accounts().unwrap();
+        let msg = tx.message().unwrap();
+        assert_eq!(accounts.len(), msg.account_keys.len());
+        assert_eq!(accounts[0], (SolanaAddress(ALICE.to_string()), true, true));
+
+        let alice = Pubkey::from_str(ALICE).unwrap();
+        let bob = Pubkey::from_str(BOB).unwrap();
+        let usdc = Pubkey::from_str(USDC).unwrap();
+        let flags = |key: Pubkey| {
+            accounts
+                .iter()
+                .find(|(address, _, _)| address.0 == key.to_string())
+                .map(|(_, is_signer, is_writable)| (*is_signer, *is_writable))
+                .unwrap()
+        };
+        assert_eq!(
+            flags(get_associated_token_address(&alice, &usdc)),
+            (false, true)
+        );
+        assert_eq!(
+            flags(get_associated_token_address(&bob, &usdc)),
+            (false, true)
+        );
+        assert_eq!(flags(bob), (false, false));
+        assert_eq!(flags(usdc), (false, false));
+        assert_eq!(flags(SPL_TOKEN_PROGRAM_ID), (false, false));
+        for (i, (_, is_signer, is_writable)) in accounts.iter().enumerate() {
+            assert_eq!(*is_signer, msg.is_signer(i));
+            assert_eq!(*is_writable, msg.is_maybe_writable(i, None));
+        }
+    }
+
+    #[test]
+    fn test_approve_revoke() {
+        let mut params = token_params(true);
+        params.has_token_account = None;
+        params.operation = Some(SolanaOperation::Approve);
+
+        let mut tx = SolanaTransaction::new(&params).unwrap();
+        let bytes = tx.sign(vec![1u8; 64], 0).unwrap();
+        let parsed = SolanaTransaction::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed, tx);
+        assert_eq!(parsed.to_bytes().unwrap(), bytes);
+
+        let from = Pubkey::from_str(ALICE).unwrap();
+        let token = Pubkey::from_str(USDC).unwrap();
+        let mut params = token_params(true);
+        params.token = None;
+        params.has_token_account = None;
+        params.decimals = None;
+        params.amount = 0;
+        params.to = SolanaAddress(get_associated_token_address(&from, &token).to_string());
+        params.operation = Some(SolanaOperation::Revoke);
+
+        let mut tx = SolanaTransaction::new(&params).unwrap();
+        let bytes = tx.sign(vec![1u8; 64], 0).unwrap();
+        assert_eq!(SolanaTransaction::from_bytes(&bytes).unwrap(), tx);
+    }
+
+    #[test]
+    fn test_freeze_thaw() {
+        let bob = Pubkey::from_str(BOB).unwrap();
+        let token = Pubkey::from_str(USDC).unwrap();
+        for operation in [SolanaOperation::FreezeAccount, SolanaOperation::ThawAccount] {
+            let params = SolanaTransactionParameters {
+                token: Some(SolanaAddress(USDC.to_string())),
+                from: SolanaAddress(ALICE.to_string()),
+                to: SolanaAddress(get_associated_token_address(&bob, &token).to_string()),
+                blockhash: BLOCKHASH.to_string(),
+                operation: Some(operation.clone()),
+                ..Default::default()
+            };
+
+            let mut tx = SolanaTransaction::new(&params).unwrap();
+            let ixs = tx.build_instructions().unwrap();
+            let expected = match operation {
+                SolanaOperation::FreezeAccount => TokenInstruction::FreezeAccount,
+                _ => TokenInstruction::ThawAccount,
+            };
+            assert_eq!(TokenInstruction::unpack(&ixs[0].data).unwrap(), expected);
+
+            let bytes = tx.sign(vec![1u8; 64], 0).unwrap();
+            let parsed = SolanaTransaction::from_bytes(&bytes).unwrap();
+            assert_eq!(parsed, tx);
+            assert_eq!(parsed.to_bytes().unwrap(), bytes);
+        }
+    }
+
+    #[test]
+    fn test_token_2022_operations() {
+        let token_program = SolanaAddress(TOKEN_2022_PROGRAM_ID.to_string());
+        let alice = Pubkey::from_str(ALICE).unwrap();
+        let bob = Pubkey::from_str(BOB).unwrap();
+        let token = Pubkey::from_str(USDC).unwrap();
+        let signers = vec![
+            SolanaAddress(Pubkey::new_unique().to_string()),
+            SolanaAddress(Pubkey::new_unique().to_string()),
+        ];
+        let ata = |owner| {
+            get_associated_token_address_with_program_id(owner, &token, &TOKEN_2022_PROGRAM_ID)
+        };
+
+        let mut params = SolanaTransactionParameters {
+            token: Some(SolanaAddress(USDC.to_string())),
+            decimals: Some(6),
+            token_program: Some(token_program.clone()),
+            from: SolanaAddress(ALICE.to_string()),
+            to: SolanaAddress(BOB.to_string()),
+            amount: 1,
+            blockhash: BLOCKHASH.to_string(),
+            fee_payer: Some(SolanaAddress(BOB.to_string())),
+            multisig_signers: Some(signers.clone()),
+            ..Default::default()
+        };
+        for operation in [
+            SolanaOperation::Approve,
+            SolanaOperation::Revoke,
+            SolanaOperation::MintTo,
+            SolanaOperation::FreezeAccount,
+            SolanaOperation::ThawAccount,
+            SolanaOperation::SetAuthority {
+                authority_type: SolanaAuthorityType::AccountOwner,
+                new_authority: None,
+            },
+            SolanaOperation::MultiTransfer {
+                recipients: vec![SolanaRecipient {
+                    to: SolanaAddress(BOB.to_string()),
+                    amount: 1,
+                }],
+            },
+            SolanaOperation::TokenBatch {
+                transfers: vec![SolanaTokenTransfer {
+                    token: SolanaAddress(USDC.to_string()),
+                    to: SolanaAddress(ata(&bob).to_string()),
+                    amount: 1,
+                    decimals: 6,
+                }],
+            },
+        ] {
+            params.operation = Some(operation.clone());
+            let tx = SolanaTransaction::new(&params).unwrap();
+            let ixs = tx.build_instructions().unwrap();
+            assert_eq!(ixs.len(), 1, "{:?}", operation);
+            assert_eq!(ixs[0].program_id, TOKEN_2022_PROGRAM_ID, "{:?}", operation);
+            let accounts: Vec<_> = ixs[0].accounts.iter().map(|meta| meta.pubkey).collect();
+            assert_eq!(
+                accounts[accounts.len() - 2..],
+                [
+                    to_pubkey(&signers[0]).unwrap(),
+                    to_pubkey(&signers[1]).unwrap()
+                ],
+                "{:?}",
+                operation
+            );
+            if matches!(
+                operation,
+                SolanaOperation::Approve
+                    | SolanaOperation::MultiTransfer { .. }
+                    | SolanaOperation::TokenBatch { .. }
+            ) {
+                assert_eq!(accounts[0], ata(&alice), "{:?}", operation);
+            }
+
+            // A single transfer parses back as a plain token transfer
+            if matches!(
+                operation,
+                SolanaOperation::MultiTransfer { .. } | SolanaOperation::TokenBatch { .. }
+            ) {
+                continue;
+            }
+            let bytes = tx.clone().sign(vec![1u8; 64], 0).unwrap();
+            let parsed = SolanaTransaction::from_bytes(&bytes).unwrap();
+            assert_eq!(parsed.params.operation, Some(operation.clone()));
+            assert_eq!(parsed.params.token_program, Some(token_program.clone()));
+            assert_eq!(parsed.params.multisig_signers, Some(signers.clone()));
+            parsed.assert_roundtrips(&bytes).unwrap();
+        }
+
+        params.operation = Some(SolanaOperation::WrapSol);
+        params.multisig_signers = None;
+        let tx = SolanaTransaction::new(&params).unwrap();
+        assert_eq!(
+            tx.build_instructions().unwrap()[1].program_id,
+            TOKEN_2022_PROGRAM_ID
+        );
+    }
+
+    #[test]
+    fn test_multisig_signers_need_token_operation() {
+        let mut params = SolanaTransactionParameters::native(
+            SolanaAddress(ALICE.to_string()),
+            SolanaAddress(BOB.to_string()),
+            1000,
+            BLOCKHASH.to_string(),
+        );
+        params.multisig_signers = Some(vec![SolanaAddress(BOB.to_string())]);
+        assert!(params.validate().is_err());
+
+        params.operation = Some(SolanaOperation::WrapSol);
+        assert!(params.validate().is_err());
+
+        params.operation = Some(SolanaOperation::Revoke);
+        assert!(params.validate().is_ok());
+    }
+
+    #[test]
+    fn test_stake() {
+        let stake = SolanaAddress(Pubkey::new_unique().to_string());
+        let vote_account = SolanaAddress(Pubkey::new_unique().to_string());
+        let authority = SolanaAddress(ALICE.to_string());
+        for params in [
+            SolanaTransactionParameters::delegate_stake(
+                stake.clone(),
+                vote_account.clone(),
+                authority.clone(),
+                BLOCKHASH.to_string(),
+            ),
+            SolanaTransactionParameters::deactivate_stake(
+                stake.clone(),
+                authority.clone(),
+                BLOCKHASH.to_string(),
+            ),
+        ] {
+            let mut tx = SolanaTransaction::new(&params).unwrap();
+            let ixs = tx.build_instructions().unwrap();
+            assert_eq!(ixs.len(), 1);
+            assert_eq!(ixs[0].program_id, STAKE_PROGRAM_ID);
+            assert_eq!(tx.required_signers().unwrap(), vec![authority.clone()]);
+
+            let bytes = tx.sign(vec![1u8; 64], 0).unwrap();
+            let parsed = SolanaTransaction::from_bytes(&bytes).unwrap();
+            assert_eq!(parsed, tx);
+            assert_eq!(parsed.to_bytes().unwrap(), bytes);
+        }
+    }
+
+    #[test]
+    fn test_transfer_with_seed() {
+        let mut params = SolanaTransactionParameters::native(
+            SolanaAddress(ALICE.to_string()),
+            SolanaAddress(BOB.to_string()),
+            1_000_000,
+            BLOCKHASH.to_string(),
+        );
+        params.operation = Some(SolanaOperation::TransferWithSeed {
+            seed: "vault".to_string(),
+            owner: SolanaAddress(SYSTEM_PROGRAM_ID.to_string()),
+        });
+
+        let mut tx = SolanaTransaction::new(&params).unwrap();
+        let msg = tx.message().unwrap();
+        let base = Pubkey::from_str(ALICE).unwrap();
+        let source = Pubkey::create_with_seed(&base, "vault", &SYSTEM_PROGRAM_ID).unwrap();
+        assert_eq!(
+            msg.account_keys[msg.instructions[0].accounts[0] as usize],
+            source
+        );
+
+        let bytes = tx.sign(vec![1u8; 64], 0).unwrap();
+        let parsed = SolanaTransaction::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed, tx);
+        assert_eq!(parsed.to_bytes().unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_chunk_by_size() {
+        let params = SolanaMultiTransferParameters {
+            from: SolanaAddress(ALICE.to_string()),
+            recipients: (0..50)
+                .map(|i| SolanaRecipient {
+                    to: SolanaAddress(Pubkey::new_unique().to_string()),
+                    amount: 1_000 + i,
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        let txs = params.chunk_by_size(BLOCKHASH.to_string()).unwrap();
+        assert!(txs.len() >= 2);
+        let mut transfers = 0;
+        for mut tx in txs {
+            assert_eq!(tx.required_signers().unwrap(), vec![params.from.clone()]);
+            transfers += tx.message().unwrap().instructions.len();
+            let bytes = tx.sign(vec![1u8; 64], 0).unwrap();
+            assert!(bytes.len() <= PACKET_DATA_SIZE);
+            let parsed = SolanaTransaction::from_bytes(&bytes).unwrap();
+            assert_eq!(parsed, tx);
+            parsed.assert_roundtrips(&bytes).unwrap();
+        }
+        assert_eq!(transfers, 50);
+
+        // Two recipients are not mistaken for a create-account
+        let mut pair = params.clone();
+        pair.recipients.truncate(2);
+        let mut tx = pair.chunk_by_size(BLOCKHASH.to_string()).unwrap().remove(0);
+        let bytes = tx.sign(vec![1u8; 64], 0).unwrap();
+        assert_eq!(SolanaTransaction::from_bytes(&bytes).unwrap(), tx);
+
+        let mut params = params;
+        params.token = Some(SolanaAddress(USDC.to_string()));
+        params.decimals = Some(6);
+        for mut tx in params.chunk_by_size(BLOCKHASH.to_string()).unwrap() {
+            let bytes = tx.sign(vec![1u8; 64], 0).unwrap();
+            assert!(bytes.len() <= PACKET_DATA_SIZE);
+            // parsed back as the equivalent token batch
+            SolanaTransaction::from_bytes(&bytes)
+                .unwrap()
+                .assert_roundtrips(&bytes)
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_token_batch() {
+        let bonk = SolanaAddress("DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263".to_string());
+        let usdc = SolanaAddress(USDC.to_string());
+        let bob = SolanaAddress(BOB.to_string());
+        let params = SolanaTokenBatchParameters {
+            from: SolanaAddress(ALICE.to_string()),
+            transfers: vec![
+                SolanaTokenTransfer {
+                    token: usdc.clone(),
+                    to: SolanaAddress(bob.associated_token_address(USDC.to_string()).unwrap()),
+                    amount: 1_000_000,
+                    decimals: 6,
+                },
+                SolanaTokenTransfer {
+                    token: bonk.clone(),
+                    to: SolanaAddress(bob.associated_token_address(bonk.0.clone()).unwrap()),
+                    amount: 42,
+                    decimals: 5,
+                },
+            ],
+        };
+
+        let mut tx = params.transaction(BLOCKHASH.to_string()).unwrap();
+        let instructions = tx.instructions().unwrap();
+        assert_eq!(instructions.len(), 2);
+        assert!(instructions
+            .iter()
+            .all(|ix| matches!(ix, SolanaInstruction::TokenTransfer { .. })));
+        assert_eq!(tx.required_signers().unwrap(), vec![params.from.clone()]);
+
+        let bytes = tx.sign(vec![1u8; 64], 0).unwrap();
+        let parsed = SolanaTransaction::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed, tx);
+        parsed.assert_roundtrips(&bytes).unwrap();
+
+        let mut params = params;
+        params.transfers.clear();
+        assert!(params.transaction(BLOCKHASH.to_string()).is_err());
+    }
+
+    #[test]
+    fn test_size_bytes() {
+        let recipients = |n| {
+            (0..n)
+                .map(|_| SolanaRecipient {
+                    to: SolanaAddress(Pubkey::new_unique().to_string()),
+                    amount: 1_000,
+                })
+                .collect()
+        };
+        let params = SolanaTransactionParameters {
+            from: SolanaAddress(ALICE.to_string()),
+            to: SolanaAddress(ALICE.to_string()),
+            blockhash: BLOCKHASH.to_string(),
+            operation: Some(SolanaOperation::MultiTransfer {
+                recipients: recipients(2),
+            }),
+            ..Default::default()
+        };
+        let mut tx = SolanaTransaction::new(&params).unwrap();
+        assert!(tx.fits_in_packet());
+        let size = tx.size_bytes().unwrap();
+        assert_eq!(tx.sign(vec![1u8; 64], 0).unwrap().len(), size);
+
+        let params = SolanaTransactionParameters {
+            operation: Some(SolanaOperation::MultiTransfer {
+                recipients: recipients(40),
+            }),
+            ..params
+        };
+        let tx = SolanaTransaction::new(&params).unwrap();
+        assert!(tx.size_bytes().unwrap() > PACKET_DATA_SIZE);
+        assert!(!tx.fits_in_packet());
+    }
+
+    #[test]
+    fn test_instructions() {
+        let tx = SolanaTransaction::from_str(TX).unwrap();
+        let instructions = tx.instructions().unwrap();
+        let alice = Pubkey::from_str(ALICE).unwrap();
+        let bob = Pubkey::from_str(BOB).unwrap();
+        let mint = tx.params.token.clone().unwrap();
+        let token = Pubkey::from_str(&mint.0).unwrap();
+        assert_eq!(
+            instructions,
+            vec![
+                SolanaInstruction::CreateAta {
+                    funder: tx.params.from.clone(),
+                    wallet: tx.params.to.clone(),
+                    mint: mint.clone(),
+                    idempotent: false,
+                    token_program: SolanaAddress(SPL_TOKEN_PROGRAM_ID.to_string()),
+                },
+                SolanaInstruction::TokenTransfer {
+                    source: SolanaAddress(
+                        get_associated_token_address(
+                            &Pubkey::from_str(&tx.params.from.0).unwrap(),
+                            &token
+                        )
+                        .to_string()
+                    ),
+                    mint: mint.clone(),
+                    destination: SolanaAddress(
+                        get_associated_token_address(
+                            &Pubkey::from_str(&tx.params.to.0).unwrap(),
+                            &token
+                        )
+                        .to_string()
+                    ),
+                    authority: tx.params.from.clone(),
+                    amount: tx.params.amount,
+                    decimals: tx.params.decimals.unwrap(),
+                    token_program: SolanaAddress(SPL_TOKEN_PROGRAM_ID.to_string()),
+                },
+            ]
+        );
+
+        let msg = tx.message().unwrap();
+        for (instruction, compiled) in instructions.iter().zip(&msg.instructions) {
+            let ix = instruction.to_instruction().unwrap();
+            assert_eq!(
+                ix.program_id,
+                msg.account_keys[compiled.program_id_index as usize]
+            );
+            assert_eq!(ix.data, compiled.data);
+        }
+
+        let ixs = vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(200_000),
+            ComputeBudgetInstruction::set_compute_unit_price(5_000),
+            Instruction::new_with_bytes(MEMO_PROGRAM_ID, b"hello", vec![]),
+            sol_transfer(&alice, &bob, 42),
+        ];
+        let msg = Message::new(&ixs, Some(&alice));
+        let decoded = msg
+            .instructions
+            .iter()
+            .map(|ix| SolanaInstruction::decode(&msg.account_keys, ix).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            decoded,
+            vec![
+                SolanaInstruction::ComputeUnitLimit { units: 200_000 },
+                SolanaInstruction::ComputeUnitPrice {
+                    micro_lamports: 5_000
+                },
+                SolanaInstruction::Memo {
+                    memo: "hello".to_string()
+                },
+                SolanaInstruction::SolTransfer {
+                    from: SolanaAddress(ALICE.to_string()),
+                    to: SolanaAddress(BOB.to_string()),
+                    lamports: 42,
+                },
+            ]
+        );
+        for (instruction, ix) in decoded.iter().zip(&ixs) {
+            assert_eq!(&instruction.to_instruction().unwrap(), ix);
+        }
+
+        let tx = SolanaTransaction::from_typed_instructions(
+            &decoded,
+            SolanaAddress(ALICE.to_string()),
+            BLOCKHASH.to_string(),
+        )
+        .unwrap();
+        assert_eq!(tx.instructions().unwrap(), decoded);
+    }
+
+    #[test]
+    fn test_token_2022_instructions() {
+        let alice = Pubkey::from_str(ALICE).unwrap();
+        let bob = Pubkey::from_str(BOB).unwrap();
+        let mint = Pubkey::from_str(USDC).unwrap();
+        let program = TOKEN_2022_PROGRAM_ID;
+        let source = get_associated_token_address_with_program_id(&alice, &mint, &program);
+        let dest = get_associated_token_address_with_program_id(&bob, &mint, &program);
+        let ixs = vec![
+            create_associated_token_account_idempotent(&alice, &bob, &mint, &program),
+            token_transfer(&program, &source, &mint, &dest, &alice, &[], 42, 6).unwrap(),
+            sync_native(&program, &dest).unwrap(),
+        ];
+        let msg = Message::new(&ixs, Some(&alice));
+        let decoded = msg
+            .instructions
+            .iter()
+            .map(|ix| SolanaInstruction::decode(&msg.account_keys, ix).unwrap())
+            .collect::<Vec<_>>();
+        let token_program = SolanaAddress(program.to_string());
+        assert_eq!(
+            decoded,
+            vec![
+                SolanaInstruction::CreateAta {
+                    funder: SolanaAddress(ALICE.to_string()),
+                    wallet: SolanaAddress(BOB.to_string()),
+                    mint: SolanaAddress(USDC.to_string()),
+                    idempotent: true,
+                    token_program: token_program.clone(),
+                },
+                SolanaInstruction::TokenTransfer {
+                    source: SolanaAddress(source.to_string()),
+                    mint: SolanaAddress(USDC.to_string()),
+                    destination: SolanaAddress(dest.to_string()),
+                    authority: SolanaAddress(ALICE.to_string()),
+                    amount: 42,
+                    decimals: 6,
+                    token_program: token_program.clone(),
+                },
+                SolanaInstruction::SyncNative {
+                    account: SolanaAddress(dest.to_string()),
+                    token_program,
+                },
+            ]
+        );
+        for (instruction, ix) in decoded.iter().zip(&ixs) {
+            assert_eq!(&instruction.to_instruction().unwrap(), ix);
+        }
+    }
+
+    #[test]
+    fn test_multi_transfer_amounts() {
+        let recipient = |amount| SolanaRecipient {
+            to: SolanaAddress(BOB.to_string()),
+            amount,
+        };
+        let mut params = SolanaMultiTransferParameters {
+            from: SolanaAddress(ALICE.to_string()),
+            recipients: vec![recipient(u64::MAX - 1), recipient(1)],
+            ..Default::default()
+        };
+        assert_eq!(params.total_amount().unwrap(), u64::MAX);
+
+        params.recipients.push(recipient(1));
+        assert!(params.total_amount().is_err());
+        assert!(params
+            .chunk_by_size(BLOCKHASH.to_string())
+            .unwrap_err()
+            .to_string()
+            .contains("overflows"));
+
+        params.recipients = vec![recipient(1), recipient(0)];
+        assert!(params
+            .chunk_by_size(BLOCKHASH.to_string())
+            .unwrap_err()
+            .to_string()
+            .contains("greater than zero"));
+    }
+
+    #[test]
+    fn test_wrap_sol() {
+        let params = SolanaTransactionParameters::wrap_sol(
+            SolanaAddress(ALICE.to_string()),
+            100_000_000,
+            BLOCKHASH.to_string(),
+        )
+        .unwrap();
+        let alice = Pubkey::from_str(ALICE).unwrap();
+        let account = get_associated_token_address(&alice, &native_mint::id());
+        assert_eq!(params.to.0, account.to_string());
+
+        let mut tx = SolanaTransaction::new(&params).unwrap();
+        assert_eq!(
+            tx.instructions().unwrap(),
+            vec![
+                SolanaInstruction::SolTransfer {
+                    from: SolanaAddress(ALICE.to_string()),
+                    to: SolanaAddress(account.to_string()),
+                    lamports: 100_000_000,
+                },
+                SolanaInstruction::SyncNative {
+                    account: SolanaAddress(account.to_string()),
+                    token_program: SolanaAddress(SPL_TOKEN_PROGRAM_ID.to_string()),
+                },
+            ]
+        );
+
+        let bytes = tx.sign(vec![1u8; 64], 0).unwrap();
+        let parsed = SolanaTransaction::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed, tx);
+        assert_eq!(parsed.to_bytes().unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_mint_to() {
+        let bob = Pubkey::from_str(BOB).unwrap();
+        let token = Pubkey::from_str(USDC).unwrap();
+        let params = SolanaTransactionParameters::mint_to(
+            SolanaAddress(USDC.to_string()),
+            SolanaAddress(get_associated_token_address(&bob, &token).to_string()),
+            SolanaAddress(ALICE.to_string()),
+            1_000_000,
+            6,
+            BLOCKHASH.to_string(),
+        );
+
+        let mut tx = SolanaTransaction::new(&params).unwrap();
+        assert_eq!(
+            tx.required_signers().unwrap(),
+            vec![SolanaAddress(ALICE.to_string())]
+        );
+        let bytes = tx.sign(vec![1u8; 64], 0).unwrap();
+        let parsed = SolanaTransaction::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed, tx);
+        assert_eq!(parsed.params.from, SolanaAddress(ALICE.to_string()));
+        assert_eq!(parsed.to_bytes().unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_set_authority() {
+        let mut params = token_params(true);
+        params.token = None;
+        params.has_token_account = None;
+        params.decimals = None;
+        params.amount = 0;
+        params.to = SolanaAddress(USDC.to_string());
+        params.operation = Some(SolanaOperation::SetAuthority {
+            authority_type: SolanaAuthorityType::MintTokens,
+            new_authority: None,
+        });
+
+        let mut tx = SolanaTransaction::new(&params).unwrap();
+        let bytes = tx.sign(vec![1u8; 64], 0).unwrap();
+        let parsed = SolanaTransaction::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed, tx);
+        assert_eq!(parsed.to_bytes().unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_multisig_authority() {
+        let signers = vec![
+            SolanaAddress(Pubkey::new_unique().to_string()),
+            SolanaAddress(Pubkey::new_unique().to_string()),
+        ];
+        for has_token_account in [true, false] {
+            let mut params = token_params(has_token_account);
+            params.from = SolanaAddress(Pubkey::new_unique().to_string());
+            params.fee_payer = Some(signers[0].clone());
+            if !has_token_account {
+                params.funder = Some(signers[0].clone());
+            }
+            params.multisig_signers = Some(signers.clone());
+
+            let tx = SolanaTransaction::new(&params).unwrap();
+            assert_eq!(tx.required_signers().unwrap(), signers);
+            let bytes = bincode::serialize(&Tx::new_unsigned(tx.message().unwrap())).unwrap();
+            let parsed = SolanaTransaction::from_bytes(&bytes).unwrap();
+            assert_eq!(parsed.params.multisig_signers, Some(signers.clone()));
+            if !has_token_account {
+                // a plain transfer parses `to` as the destination token account
+                assert_eq!(parsed.params, params);
+                parsed.assert_roundtrips(&bytes).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_funder_differs_from_authority() {
+        let mut params = token_params(false);
+        params.funder = Some(SolanaAddress(BOB.to_string()));
+
+        let tx = SolanaTransaction::new(&params).unwrap();
+        assert_eq!(
+            tx.required_signers().unwrap(),
+            vec![
+                SolanaAddress(ALICE.to_string()),
+                SolanaAddress(BOB.to_string())
+            ]
+        );
+
+        let bytes = bincode::serialize(&Tx::new_unsigned(tx.message().unwrap())).unwrap();
+        let parsed = SolanaTransaction::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed.params.from, SolanaAddress(ALICE.to_string()));
+        assert_eq!(parsed.params.funder, Some(SolanaAddress(BOB.to_string())));
+        assert_eq!(parsed.params.to, SolanaAddress(BOB.to_string()));
+        assert_eq!(parsed.params.has_token_account, Some(false));
+    }
+
+    #[test]
+    fn test_fee_payer_differs_from_authority() {
+        let mut params = token_params(false);
+        params.fee_payer = Some(SolanaAddress(BOB.to_string()));
+
+        let tx = SolanaTransaction::new(&params).unwrap();
+        let msg = tx.message().unwrap();
+        assert_eq!(msg.account_keys[0].to_string(), BOB);
+        assert_eq!(
+            tx.required_signers().unwrap(),
+            vec![
+                SolanaAddress(BOB.to_string()),
+                SolanaAddress(ALICE.to_string())
+            ]
+        );
+
+        let bytes = bincode::serialize(&Tx::new_unsigned(msg)).unwrap();
+        let parsed = SolanaTransaction::from_bytes(&bytes).unwrap();
+        assert_eq!(
+            parsed.params.fee_payer,
+            Some(SolanaAddress(BOB.to_string()))
+        );
+        assert_eq!(parsed.params.from, SolanaAddress(ALICE.to_string()));
+        assert_eq!(parsed.params, params);
+        assert_eq!(parsed.message().unwrap(), tx.message().unwrap());
+        parsed.assert_roundtrips(&bytes).unwrap();
+    }
+
+    #[test]
+    fn test_add_signature() {
+        let payer = Keypair::new();
+        let account = Keypair::new();
+        let params = SolanaTransactionParameters::create_account(
+            SolanaAddress(payer.pubkey().to_string()),
+            SolanaAddress(account.pubkey().to_string()),
+            1_000_000,
+            0,
+            SolanaAddress::system_program(),
+            BLOCKHASH.to_string(),
+        );
+        let mut tx = SolanaTransaction::new(&params).unwrap();
+        let msg = tx.to_bytes().unwrap();
+        assert!(!tx.is_fully_signed());
+
+        // the second signer signs first
+        tx.add_signature(
+            SolanaAddress(account.pubkey().to_string()),
+            account.sign_message(&msg).as_ref().to_vec(),
+        )
+        .unwrap();
+        assert!(!tx.is_fully_signed());
+        assert!(tx.finalize().is_err());
+        assert!(tx
+            .add_signature(SolanaAddress(BOB.to_string()), vec![1u8; 64])
+            .is_err());
+
+        tx.add_signature(
+            SolanaAddress(payer.pubkey().to_string()),
+            payer.sign_message(&msg).as_ref().to_vec(),
+        )
+        .unwrap();
+        assert!(tx.is_fully_signed());
+
+        let bytes = tx.finalize().unwrap();
+        let blockhash = Hash::from_str(BLOCKHASH).unwrap();
+        let expected = Tx::new(&[&payer, &account], tx.message().unwrap(), blockhash);
+        assert_eq!(bytes, bincode::serialize(&expected).unwrap());
+        assert!(bincode::deserialize::<Tx>(&bytes).unwrap().verify().is_ok());
+    }
+
+    #[test]
+    fn test_multi_signature() {
+        let payer = Keypair::new();
+        let account = Keypair::new();
+        let params = SolanaTransactionParameters::create_account(
+            SolanaAddress(payer.pubkey().to_string()),
+            SolanaAddress(account.pubkey().to_string()),
+            1_000_000,
+            0,
+            SolanaAddress(SYSTEM_PROGRAM_ID.to_string()),
+            BLOCKHASH.to_string(),
+        );
+        let tx = SolanaTransaction::new(&params).unwrap();
+        let blockhash = Hash::from_str(BLOCKHASH).unwrap();
+        let signed = Tx::new(&[&payer, &account], tx.message().unwrap(), blockhash);
+        let bytes = bincode::serialize(&signed).unwrap();
+
+        let parsed = SolanaTransaction::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed.signature.len(), 2);
+        assert_eq!(parsed.signature[1], signed.signatures[1].as_ref());
+        assert_eq!(parsed.to_bytes().unwrap(), bytes);
+        assert_eq!(Tx::try_from(&parsed).unwrap(), signed);
+        assert_eq!(
+            serde_json::from_str::<SolanaTransaction>(&serde_json::to_string(&parsed).unwrap())
+                .unwrap(),
+            parsed
+        );
+    }
+
+    #[test]
+    fn test_token_ui() {
+        let params = SolanaTransactionParameters::token_ui(
+            SolanaAddress(USDC.to_string()),
+            true,
+            SolanaAddress(ALICE.to_string()),
+            SolanaAddress(BOB.to_string()),
+            "1",
+            6,
+            BLOCKHASH.to_string(),
+        )
+        .unwrap();
+        assert_eq!(params, token_params(true));
+
+        assert!(SolanaTransactionParameters::token_ui(
+            SolanaAddress(USDC.to_string()),
+            true,
+            SolanaAddress(ALICE.to_string()),
+            SolanaAddress(BOB.to_string()),
+            "0.0000001",
+            6,
+            BLOCKHASH.to_string(),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_to_bytes_for_simulation() {
+        let payer = Keypair::new();
+        let mut params = SolanaTransactionParameters::native(
+            SolanaAddress(ALICE.to_string()),
+            SolanaAddress(BOB.to_string()),
+            1000,
+            BLOCKHASH.to_string(),
+        );
+        params.fee_payer = Some(SolanaAddress(payer.pubkey().to_string()));
+        let tx = SolanaTransaction::new(&params).unwrap();
+
+        let bytes = tx.to_bytes_for_simulation().unwrap();
+        let decoded = bincode::deserialize::<VersionedTransaction>(&bytes).unwrap();
+        assert_eq!(
+            decoded.signatures.len(),
+            decoded.message.header().num_required_signatures as usize
+        );
+        assert_eq!(decoded.signatures.len(), 2);
+        assert!(decoded
+            .signatures
+            .iter()
+            .all(|sig| *sig == Signature::default()));
+        assert_eq!(decoded.message.serialize(), tx.to_bytes().unwrap());
+    }
+
+    #[test]
+    fn test_order_signatures() {
+        let from = Keypair::new();
+        let payer = Keypair::new();
+        let mut params = SolanaTransactionParameters::native(
+            SolanaAddress(from.pubkey().to_string()),
+            SolanaAddress(BOB.to_string()),
+            1000,
+            BLOCKHASH.to_string(),
+        );
+        params.fee_payer = Some(SolanaAddress(payer.pubkey().to_string()));
+        let mut tx = SolanaTransaction::new(&params).unwrap();
+        let message = tx.to_bytes().unwrap();
+
+        let blockhash = Hash::from_str(BLOCKHASH).unwrap();
+        let ixs = [sol_transfer(
+            &from.pubkey(),
+            &Pubkey::from_str(BOB).unwrap(),
+            1000,
+        )];
+        let expected =
+            Tx::new_signed_with_payer(&ixs, Some(&payer.pubkey()), &[&from, &payer], blockhash);
+
+        // Insertion order of the map is irrelevant
+        let mut signatures = HashMap::new();
+        signatures.insert(
+            SolanaAddress(from.pubkey().to_string()),
+            from.sign_message(&message).as_ref().to_vec(),
+        );
+        let signers = tx.required_signers().unwrap();
+        assert_eq!(
+            order_signatures(&signers, &signatures),
+            vec![vec![0u8; 64], expected.signatures[1].as_ref().to_vec()]
+        );
+        signatures.insert(
+            SolanaAddress(payer.pubkey().to_string()),
+            payer.sign_message(&message).as_ref().to_vec(),
+        );
+        tx.set_signatures(&signatures).unwrap();
+        assert_eq!(
+            tx.to_bytes().unwrap(),
+            bincode::serialize(&expected).unwrap()
+        );
+
+        signatures.insert(SolanaAddress(BOB.to_string()), vec![1u8; 64]);
+        assert!(tx.set_signatures(&signatures).is_err());
+    }
+
+    #[test]
+    fn test_set_fee_payer() {
+        let mut tx = SolanaTransaction::new(&token_params(true)).unwrap();
+        tx.sign(vec![1u8; 64], 0).unwrap();
+
+        let relayer = SolanaAddress(Pubkey::new_unique().to_string());
+        tx.set_fee_payer(relayer.clone());
+        assert!(!tx.is_signed());
+        assert_eq!(
+            tx.required_signers().unwrap(),
+            vec![relayer, SolanaAddress(ALICE.to_string())]
+        );
+
+        tx.set_fee_payer(SolanaAddress(ALICE.to_string()));
+        assert_eq!(tx.params, token_params(true));
+    }
+
+    #[test]
+    fn test_compute_budget() {
+        // the shape produced by most wallets: priority fee, create ATA, transfer
+        let from = Pubkey::from_str(ALICE).unwrap();
+        let to = Pubkey::from_str(BOB).unwrap();
+        let token = Pubkey::from_str(USDC).unwrap();
+        let ixs = vec![
+            ComputeBudgetInstruction::set_compute_unit_price(100_000),
+            create_associated_token_account(&from, &to, &token, &id()),
+            token_transfer(
+                &id(),
+                &get_associated_token_address(&from, &token),
+                &token,
+                &get_associated_token_address(&to, &token),
+                &from,
+                &[],
+                1_000_000,
+                6,
+            )
+            .unwrap(),
+        ];
+        let blockhash = Hash::from_str(BLOCKHASH).unwrap();
+        let msg = Message::new_with_blockhash(&ixs, Some(&from), &blockhash);
+        let bytes = bincode::serialize(&Tx::new_unsigned(msg)).unwrap();
+
+        let tx = SolanaTransaction::from_bytes(&bytes).unwrap();
+        assert_eq!(tx.params.token, Some(SolanaAddress(USDC.to_string())));
+        assert_eq!(tx.params.from, SolanaAddress(ALICE.to_string()));
+        assert_eq!(tx.params.to, SolanaAddress(BOB.to_string()));
+        assert_eq!(tx.params.amount, 1_000_000);
+        assert_eq!(tx.params.compute_unit_price, Some(100_000));
+        assert_eq!(tx.params.compute_unit_limit, None);
+        tx.assert_roundtrips(&bytes).unwrap();
+
+        let mut params = token_params(false);
+        params.compute_unit_limit = Some(50_000);
+        params.compute_unit_price = Some(1);
+        let mut tx = SolanaTransaction::new(&params).unwrap();
+        assert_eq!(tx.message().unwrap().instructions.len(), 4);
+        let bytes = tx.sign(vec![1u8; 64], 0).unwrap();
+        assert_eq!(SolanaTransaction::from_bytes(&bytes).unwrap(), tx);
+    }
+
+    #[test]
+    fn test_unknown_instruction() {
+        let from = Pubkey::from_str(ALICE).unwrap();
+        let to = Pubkey::from_str(BOB).unwrap();
+        let program = Pubkey::new_unique();
+        let ixs = [
+            sol_transfer(&from, &to, 1000),
+            Instruction::new_with_bytes(
+                program,
+                &[1, 2, 3],
+                vec![AccountMeta::new_readonly(to, false)],
+            ),
+        ];
+        let blockhash = Hash::from_str(BLOCKHASH).unwrap();
+        let msg = Message::new_with_blockhash(&ixs, Some(&from), &blockhash);
+        let bytes = bincode::serialize(&Tx::new_unsigned(msg)).unwrap();
+
+        let tx = SolanaTransaction::from_bytes(&bytes).unwrap();
+        assert_eq!(tx.params.from, SolanaAddress(ALICE.to_string()));
+        assert_eq!(tx.params.to, SolanaAddress(BOB.to_string()));
+        assert_eq!(tx.params.amount, 1000);
+        assert_eq!(
+            tx.unknown_instructions,
+            vec![(
+                SolanaAddress(program.to_string()),
+                vec![SolanaAddress(BOB.to_string())],
+                vec![1, 2, 3]
+            )]
+        );
+
+        assert!(SolanaTransaction::from_bytes_strict(&bytes).is_err());
+
+        // Re-serializing or re-signing would drop the unknown instruction
+        let mut tx = tx;
+        assert!(tx
+            .to_bytes()
+            .unwrap_err()
+            .to_string()
+            .contains(&program.to_string()));
+        assert!(tx.sign(vec![1u8; 64], 0).is_err());
+        assert!(tx.to_bytes_for_simulation().is_err());
+        assert!(tx.approval_json().is_err());
+        assert!(tx.export_unsigned().is_err());
+        tx.unknown_instructions.clear();
+        assert!(tx.to_bytes().is_ok());
+
+        let msg = Message::new_with_blockhash(&ixs[1..], Some(&from), &blockhash);
+        let bytes = bincode::serialize(&Tx::new_unsigned(msg)).unwrap();
+        assert!(SolanaTransaction::from_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_parse_zero_amount() {
+        let from = Pubkey::from_str(ALICE).unwrap();
+        let to = Pubkey::from_str(BOB).unwrap();
+        let ix = sol_transfer(&from, &to, 0);
+        let blockhash = Hash::from_str(BLOCKHASH).unwrap();
+        let msg = Message::new_with_blockhash(&[ix], Some(&from), &blockhash);
+        let bytes = bincode::serialize(&Tx::new_unsigned(msg)).unwrap();
+
+        let tx = SolanaTransaction::from_bytes(&bytes).unwrap();
+        assert_eq!(tx.params.amount, 0);
+        assert_eq!(tx.to_bytes().unwrap(), bytes);
+        assert!(SolanaTransaction::new(&tx.params).is_err());
+    }
+
+    #[test]
+    fn test_legacy_token_transfer() {
+        let owner = Pubkey::from_str(ALICE).unwrap();
+        let token = Pubkey::from_str(USDC).unwrap();
+        let source = get_associated_token_address(&owner, &token);
+        let dest = get_associated_token_address(&Pubkey::from_str(BOB).unwrap(), &token);
+        let ix =
+            spl_token::instruction::transfer(&id(), &source, &dest, &owner, &[], 2500).unwrap();
+        let blockhash = Hash::from_str(BLOCKHASH).unwrap();
+        let msg = Message::new_with_blockhash(&[ix], Some(&owner), &blockhash);
+        let bytes = bincode::serialize(&Tx::new_unsigned(msg)).unwrap();
+
+        let tx = SolanaTransaction::from_bytes(&bytes).unwrap();
+        assert_eq!(tx.params.token, None);
+        assert_eq!(tx.params.decimals, None);
+        assert_eq!(
+            tx.params.operation,
+            Some(SolanaOperation::UncheckedTokenTransfer {
+                source: SolanaAddress(source.to_string())
+            })
+        );
+        assert_eq!(tx.params.from, SolanaAddress(ALICE.to_string()));
+        assert_eq!(tx.params.to, SolanaAddress(dest.to_string()));
+        assert_eq!(tx.params.amount, 2500);
+        assert_eq!(tx.to_bytes().unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_from_instructions() {
+        let from = Pubkey::from_str(ALICE).unwrap();
+        let to = Pubkey::from_str(BOB).unwrap();
+        let mut tx = SolanaTransaction::from_instructions(
+            vec![sol_transfer(&from, &to, 1000)],
+            SolanaAddress(ALICE.to_string()),
+            BLOCKHASH.to_string(),
+        )
+        .unwrap();
+        let params = SolanaTransactionParameters::native(
+            SolanaAddress(ALICE.to_string()),
+            SolanaAddress(BOB.to_string()),
+            1000,
+            BLOCKHASH.to_string(),
+        );
+        let mut expected = SolanaTransaction::new(&params).unwrap();
+        assert_eq!(tx.to_bytes().unwrap(), expected.to_bytes().unwrap());
+
+        let bytes = tx.sign(vec![1u8; 64], 0).unwrap();
+        assert_eq!(bytes, expected.sign(vec![1u8; 64], 0).unwrap());
+        assert_eq!(SolanaTransaction::from_bytes(&bytes).unwrap(), expected);
+
+        assert!(SolanaTransaction::from_instructions(
+            vec![],
+            SolanaAddress(ALICE.to_string()),
+            BLOCKHASH.to_string()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_from_message_base64() {
+        let tx = SolanaTransaction::new(&token_params(false)).unwrap();
+        let message = BASE64_STANDARD.encode(tx.to_bytes().unwrap());
+        assert_eq!(
+            SolanaTransaction::from_message_base64(&message).unwrap(),
+            tx
+        );
+
+        let mut params = SolanaTransactionParameters::native(
+            SolanaAddress(ALICE.to_string()),
+            SolanaAddress(BOB.to_string()),
+            1000,
+            BLOCKHASH.to_string(),
+        );
+        params.lookup_tables = Some(vec![]);
+        let tx = SolanaTransaction::new(&params).unwrap();
+        let message = BASE64_STANDARD.encode(tx.to_bytes().unwrap());
+        assert_eq!(
+            SolanaTransaction::from_message_base64(&message).unwrap(),
+            tx
+        );
+
+        assert!(SolanaTransaction::from_message_base64("not base64").is_err());
+    }
+
+    #[test]
+    fn test_create_ata_transfer_order() {
+        let params = token_params(false);
+        let tx = SolanaTransaction::new(&params).unwrap();
+        let mut ixs = tx.build_instructions().unwrap();
+        let fee_payer = Pubkey::from_str(ALICE).unwrap();
+        let blockhash = Hash::from_str(BLOCKHASH).unwrap();
+
+        ixs.reverse();
+        let msg = Message::new_with_blockhash(&ixs, Some(&fee_payer), &blockhash);
+        let bytes = bincode::serialize(&Tx::new_unsigned(msg)).unwrap();
+        assert_eq!(
+            SolanaTransaction::from_bytes(&bytes).unwrap().params,
+            params
+        );
+
+        ixs.insert(
+            1,
+            Instruction::new_with_bytes(MEMO_PROGRAM_ID, b"order 7", vec![]),
+        );
+        ixs.insert(0, ComputeBudgetInstruction::set_compute_unit_price(5));
+        let msg = Message::new_with_blockhash(&ixs, Some(&fee_payer), &blockhash);
+        let bytes = bincode::serialize(&Tx::new_unsigned(msg)).unwrap();
+        let parsed = SolanaTransaction::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed.params.compute_unit_price, Some(5));
+        assert_eq!(parsed.unknown_instructions.len(), 1);
+        assert_eq!(
+            SolanaTransactionParameters {
+                compute_unit_price: None,
+                ..parsed.params
+            },
+            params
+        );
+
+        let ixs = [
+            sol_transfer(&fee_payer, &Pubkey::from_str(BOB).unwrap(), 1),
+            ixs[1].clone(),
+        ];
+        let msg = Message::new_with_blockhash(&ixs, Some(&fee_payer), &blockhash);
+        let bytes = bincode::serialize(&Tx::new_unsigned(msg)).unwrap();
+        assert!(SolanaTransaction::from_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_transfer_fee() {
+        let mut params = token_params(true);
+        params.token_program = Some(SolanaAddress::token_2022_program());
+        params.transfer_fee = Some(1_000);
+        let mut tx = SolanaTransaction::new(&params).unwrap();
+        let ixs = tx.build_instructions().unwrap();
+        assert_eq!(ixs.len(), 1);
+        assert_eq!(ixs[0].program_id, TOKEN_2022_PROGRAM_ID);
+        assert_eq!(
+            parse_transfer_checked(&ixs[0].data).unwrap(),
+            (1_000_000, 6, Some(1_000))
+        );
+
+        let bytes = tx.sign(vec![1u8; 64], 0).unwrap();
+        let parsed = SolanaTransaction::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed.params.transfer_fee, Some(1_000));
+        assert_eq!(parsed.params.token_program, params.token_program);
+        assert_eq!(parsed.params.amount, 1_000_000);
+
+        params.has_token_account = Some(false);
+        let mut tx = SolanaTransaction::new(&params).unwrap();
+        let bytes = tx.sign(vec![1u8; 64], 0).unwrap();
+        let parsed = SolanaTransaction::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed.params, params);
+        assert_eq!(parsed.to_bytes().unwrap(), bytes);
+
+        params.token_program = None;
+        assert!(SolanaTransaction::new(&params).is_err());
+    }
+
+    #[test]
+    fn test_idempotent_create_ata_transfer() {
+        let mut params = token_params(true);
+        params.has_token_account = None;
+        let mut tx = SolanaTransaction::new(&params).unwrap();
+        let ixs = tx.build_instructions().unwrap();
+        assert_eq!(ixs.len(), 2);
+        assert_eq!(ixs[0].program_id, ASSOCIATED_TOKEN_PROGRAM_ID);
+        assert_eq!(ixs[0].data, vec![1]);
+        assert_eq!(ixs[1].program_id, SPL_TOKEN_PROGRAM_ID);
+        assert_eq!(
+            ixs[1..],
+            SolanaTransaction::new(&token_params(false))
+                .unwrap()
+                .build_instructions()
+                .unwrap()[1..]
+        );
+
+        let bytes = tx.sign(vec![1u8; 64], 0).unwrap();
+        let parsed = SolanaTransaction::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed.params, params);
+        assert_eq!(parsed.to_bytes().unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_token_2022_create_ata_transfer() {
+        let mut params = token_params(false);
+        params.token_program = Some(SolanaAddress::token_2022_program());
+        let tx = SolanaTransaction::new(&params).unwrap();
+        let ixs = tx.build_instructions().unwrap();
+        assert_eq!(ixs[0].program_id, ASSOCIATED_TOKEN_PROGRAM_ID);
+        assert_eq!(ixs[0].accounts[5].pubkey, TOKEN_2022_PROGRAM_ID);
+        assert_eq!(ixs[1].program_id, TOKEN_2022_PROGRAM_ID);
+
+        let dest = SolanaAddress(BOB.to_string())
+            .associated_token_address_with_format(USDC.to_string(), &SolanaFormat::Token2022)
+            .unwrap();
+        assert_eq!(ixs[0].accounts[1].pubkey.to_string(), dest);
+        assert_eq!(ixs[1].accounts[2].pubkey.to_string(), dest);
+
+        let bytes = tx.clone().sign(vec![1u8; 64], 0).unwrap();
+        let parsed = SolanaTransaction::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed.params, params);
+        assert_eq!(parsed.to_bytes().unwrap(), bytes);
+
+        params.token_program = Some(SolanaAddress(ALICE.to_string()));
+        assert!(SolanaTransaction::new(&params).is_err());
+    }
+
+    #[test]
+    fn test_from_bytes_strict() {
+        let from = Pubkey::from_str(ALICE).unwrap();
+        let to = Pubkey::from_str(BOB).unwrap();
+        let ixs = [
+            ComputeBudgetInstruction::set_compute_unit_limit(10_000),
+            ComputeBudgetInstruction::request_heap_frame(64 * 1024),
+            sol_transfer(&from, &to, 1000),
+            Instruction::new_with_bytes(MEMO_PROGRAM_ID, b"invoice 42", vec![]),
+        ];
+        let blockhash = Hash::from_str(BLOCKHASH).unwrap();
+        let msg = Message::new_with_blockhash(&ixs, Some(&from), &blockhash);
+        let bytes = bincode::serialize(&Tx::new_unsigned(msg)).unwrap();
+
+        // The heap frame request is not modelled
+        assert!(SolanaTransaction::from_bytes_strict(&bytes).is_err());
+        let tx = SolanaTransaction::from_bytes(&bytes).unwrap();
+        assert_eq!(tx.params.memo, Some("invoice 42".to_string()));
+        assert_eq!(tx.unknown_instructions.len(), 1);
+
+        let ixs = [&ixs[0], &ixs[2], &ixs[3]].map(|ix| ix.clone());
+        let msg = Message::new_with_blockhash(&ixs, Some(&from), &blockhash);
+        let bytes = bincode::serialize(&Tx::new_unsigned(msg)).unwrap();
+        let tx = SolanaTransaction::from_bytes_strict(&bytes).unwrap();
+        assert_eq!(tx, SolanaTransaction::from_bytes(&bytes).unwrap());
+        assert_eq!(tx.params.compute_unit_limit, Some(10_000));
+        assert_eq!(tx.params.memo, Some("invoice 42".to_string()));
+        assert!(tx.unknown_instructions.is_empty());
+        tx.assert_roundtrips(&bytes).unwrap();
+    }
+
+    #[test]
+    fn test_assert_roundtrips() {
+        let mut tx = SolanaTransaction::new(&token_params(false)).unwrap();
+        let bytes = tx.sign(vec![1u8; 64], 0).unwrap();
+        let parsed = SolanaTransaction::from_bytes(&bytes).unwrap();
+        assert!(parsed.assert_roundtrips(&bytes).is_ok());
+
+        // A SOL transfer with the system program listed before the recipient
+        let from = Pubkey::from_str(ALICE).unwrap();
+        let to = Pubkey::from_str(BOB).unwrap();
+        let ix = sol_transfer(&from, &to, 1000);
+        let msg = Message::new_with_compiled_instructions(
+            1,
+            0,
+            0,
+            vec![from, SYSTEM_PROGRAM_ID, to],
+            Hash::from_str(BLOCKHASH).unwrap(),
+            vec![CompiledInstruction::new_from_raw_parts(
+                1,
+                ix.data,
+                vec![0, 2],
+            )],
+        );
+        let bytes = bincode::serialize(&Tx::new_unsigned(msg)).unwrap();
+        let parsed = SolanaTransaction::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed.params.to, SolanaAddress(BOB.to_string()));
+        let err = parsed.assert_roundtrips(&bytes).unwrap_err();
+        assert!(err.to_string().contains("header differs"));
+    }
+
+    #[test]
+    fn test_v0_transfer() {
+        let from = Pubkey::from_str(ALICE).unwrap();
+        let to = Pubkey::from_str(BOB).unwrap();
+        let blockhash = Hash::from_str(BLOCKHASH).unwrap();
+        let ix = sol_transfer(&from, &to, 1000);
+        let msg = v0::Message::try_compile(&from, &[ix], &[], blockhash).unwrap();
+        let tx = VersionedTransaction {
+            signatures: vec![Signature::from([1u8; 64])],
+            message: VersionedMessage::V0(msg),
+        };
+        let bytes = bincode::serialize(&tx).unwrap();
+        assert!(bincode::deserialize::<Tx>(&bytes).is_err());
+
+        let tx = SolanaTransaction::from_bytes(&bytes).unwrap();
+        assert_eq!(tx.params.from, SolanaAddress(ALICE.to_string()));
+        assert_eq!(tx.params.to, SolanaAddress(BOB.to_string()));
+        assert_eq!(tx.params.amount, 1000);
+        assert_eq!(tx.params.blockhash, BLOCKHASH);
+        assert_eq!(tx.signature, vec![vec![1u8; 64]]);
+        assert_eq!(tx.params.lookup_tables, Some(vec![]));
+        assert_eq!(tx.to_bytes().unwrap(), bytes);
+        tx.assert_roundtrips(&bytes).unwrap();
+
+        // The same transfer as a legacy transaction
+        let legacy = Tx::try_from(&tx.clone_unsigned());
+        assert!(legacy.is_err());
+        let mut params = tx.params.clone();
+        params.lookup_tables = None;
+        let mut legacy = SolanaTransaction::new(&params).unwrap();
+        let legacy = legacy.sign(vec![1u8; 64], 0).unwrap();
+        let err = tx.assert_roundtrips(&legacy).unwrap_err();
+        assert!(err.to_string().contains("version differs"));
+    }
+
+    #[test]
+    fn test_lookup_table() {
+        let table = SolanaLookupTable {
+            address: SolanaAddress(Pubkey::new_unique().to_string()),
+            addresses: vec![SolanaAddress(BOB.to_string())],
+        };
+        let mut params = token_params(true);
+        params.token = None;
+        params.has_token_account = None;
+        params.decimals = None;
+        params.lookup_tables = Some(vec![table.clone()]);
+
+        let tx = SolanaTransaction::new(&params).unwrap();
+        let msg = match tx.versioned_message().unwrap() {
+            VersionedMessage::V0(msg) => msg,
+            VersionedMessage::Legacy(_) => panic!("expected a v0 message"),
+        };
+        let bob = Pubkey::from_str(BOB).unwrap();
+        assert!(!msg.account_keys.contains(&bob));
+        assert_eq!(msg.address_table_lookups.len(), 1);
+        assert_eq!(
+            msg.address_table_lookups[0].account_key.to_string(),
+            table.address.0
+        );
+        assert_eq!(msg.address_table_lookups[0].writable_indexes, vec![0]);
+
+        // The approval covers the v0 message, the recipient being loaded
+        // from the table
+        let json = tx.approval_json().unwrap();
+        assert!(json.contains(BOB));
+        assert!(json.contains(&table.address.0));
+        let mut legacy = tx.clone();
+        legacy.params.lookup_tables = None;
+        assert_ne!(
+            tx.approval_digest().unwrap(),
+            legacy.approval_digest().unwrap()
+        );
+        assert!(json.contains("address_table_lookups"));
+        assert!(!legacy
+            .approval_json()
+            .unwrap()
+            .contains("address_table_lookups"));
+
+        assert!(Tx::try_from(&tx).is_err());
+    }
+
+    #[test]
+    fn test_create_nonce_account() {
+        let nonce_account = SolanaAddress(Pubkey::new_unique().to_string());
+        let params = SolanaTransactionParameters::create_nonce_account(
+            SolanaAddress(ALICE.to_string()),
+            nonce_account.clone(),
+            SolanaAddress(BOB.to_string()),
+            1_447_680,
+            BLOCKHASH.to_string(),
+        );
+
+        let tx = SolanaTransaction::new(&params).unwrap();
+        let msg = tx.message().unwrap();
+        assert_eq!(msg.instructions.len(), 2);
+        assert_eq!(
+            tx.required_signers().unwrap(),
+            vec![SolanaAddress(ALICE.to_string()), nonce_account]
+        );
+
+        let bytes = bincode::serialize(&Tx::new_unsigned(msg)).unwrap();
+        let parsed = SolanaTransaction::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed.params, params);
+    }
+
+    #[test]
+    fn test_create_account() {
+        let account = SolanaAddress(Pubkey::new_unique().to_string());
+        let params = SolanaTransactionParameters::create_account(
+            SolanaAddress(ALICE.to_string()),
+            account.clone(),
+            1_000_000,
+            165,
+            SolanaAddress(SPL_TOKEN_PROGRAM_ID.to_string()),
+            BLOCKHASH.to_string(),
+        );
+
+        let tx = SolanaTransaction::new(&params).unwrap();
+        let instructions = tx.instructions().unwrap();
+        assert_eq!(instructions.len(), 2);
+        match &instructions[0] {
+            SolanaInstruction::Other((program, accounts, data)) => {
+                assert_eq!(program.0, SYSTEM_PROGRAM_ID.to_string());
+                assert_eq!(
+                    accounts,
+                    &vec![SolanaAddress(ALICE.to_string()), account.clone()]
+                );
+                assert_eq!(
+                    bincode::deserialize::<SystemInstruction>(data).unwrap(),
+                    SystemInstruction::CreateAccount {
+                        lamports: Rent::default().minimum_balance(165),
+                        space: 165,
+                        owner: SPL_TOKEN_PROGRAM_ID,
+                    }
+                );
+            }
+            ix => panic!("unexpected instruction {:?}", ix),
+        }
+        assert_eq!(
+            instructions[1],
+            SolanaInstruction::SolTransfer {
+                from: SolanaAddress(ALICE.to_string()),
+                to: account.clone(),
+                lamports: 1_000_000,
+            }
+        );
+        assert_eq!(
+            tx.required_signers().unwrap(),
+            vec![SolanaAddress(ALICE.to_string()), account]
+        );
+
+        let bytes = bincode::serialize(&Tx::new_unsigned(tx.message().unwrap())).unwrap();
+        let parsed = SolanaTransaction::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed.params, params);
+    }
+
+    #[test]
+    fn test_create_account_with_seed() {
+        let base = Pubkey::new_unique();
+        let params = SolanaTransactionParameters::create_account_with_seed(
+            SolanaAddress(ALICE.to_string()),
+            SolanaAddress(base.to_string()),
+            "deposit-0001".to_string(),
+            2_039_280,
+            165,
+            SolanaAddress(SPL_TOKEN_PROGRAM_ID.to_string()),
+            BLOCKHASH.to_string(),
+        )
+        .unwrap();
+        let derived =
+            Pubkey::create_with_seed(&base, "deposit-0001", &SPL_TOKEN_PROGRAM_ID).unwrap();
+        assert_eq!(params.to.0, derived.to_string());
+
+        let tx = SolanaTransaction::new(&params).unwrap();
+        assert_eq!(
+            tx.required_signers().unwrap(),
+            vec![
+                SolanaAddress(ALICE.to_string()),
+                SolanaAddress(base.to_string())
+            ]
+        );
+        let bytes = bincode::serialize(&Tx::new_unsigned(tx.message().unwrap())).unwrap();
+        let parsed = SolanaTransaction::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed.params, params);
+        parsed.assert_roundtrips(&bytes).unwrap();
+
+        let mut params = params;
+        params.to = SolanaAddress(BOB.to_string());
+        assert!(SolanaTransaction::new(&params).is_err());
+    }
+
+    #[test]
+    fn test_allocate() {
+        let params = SolanaTransactionParameters {
+            from: SolanaAddress(ALICE.to_string()),
+            to: SolanaAddress(ALICE.to_string()),
+            blockhash: BLOCKHASH.to_string(),
+            operation: Some(SolanaOperation::Allocate { space: 165 }),
+            ..Default::default()
+        };
+        let tx = SolanaTransaction::new(&params).unwrap();
+        let msg = tx.message().unwrap();
+        assert_eq!(
+            msg.instructions[0].data,
+            bincode::serialize(&SystemInstruction::Allocate { space: 165 }).unwrap()
+        );
+
+        let bytes = bincode::serialize(&Tx::new_unsigned(msg)).unwrap();
+        let parsed = SolanaTransaction::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed.params, params);
+        parsed.assert_roundtrips(&bytes).unwrap();
+    }
+
+    #[test]
+    fn test_assign() {
+        let params = SolanaTransactionParameters {
+            from: SolanaAddress(ALICE.to_string()),
+            to: SolanaAddress(ALICE.to_string()),
+            blockhash: BLOCKHASH.to_string(),
+            operation: Some(SolanaOperation::Assign {
+                owner: SolanaAddress::token_program(),
+            }),
+            ..Default::default()
+        };
+        let tx = SolanaTransaction::new(&params).unwrap();
+        let msg = tx.message().unwrap();
+        assert_eq!(
+            msg.instructions[0].data,
+            bincode::serialize(&SystemInstruction::Assign {
+                owner: SPL_TOKEN_PROGRAM_ID
+            })
+            .unwrap()
+        );
+
+        let bytes = bincode::serialize(&Tx::new_unsigned(msg)).unwrap();
+        let parsed = SolanaTransaction::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed.params, params);
+        parsed.assert_roundtrips(&bytes).unwrap();
+    }
+
+    #[test]
+    fn test_native_to_account() {
+        let from = SolanaAddress(ALICE.to_string());
+        let to = SolanaAddress(BOB.to_string());
+        let native = |amount, has_account| {
+            SolanaTransactionParameters::native_to_account(
+                from.clone(),
+                to.clone(),
+                amount,
+                has_account,
+                890_880,
+                BLOCKHASH.to_string(),
+            )
+        };
+        let plain = SolanaTransactionParameters::native(
+            from.clone(),
+            to.clone(),
+            1_000_000,
+            BLOCKHASH.to_string(),
+        );
+
+        assert_eq!(native(1_000_000, Some(true)).unwrap(), plain);
+        assert_eq!(native(1, Some(true)).unwrap().amount, 1);
+        assert_eq!(native(1_000_000, Some(false)).unwrap(), plain);
+        assert!(native(890_879, Some(false))
+            .unwrap_err()
+            .to_string()
+            .contains("rent-exempt minimum"));
+        assert!(native(1_000_000, None)
+            .unwrap_err()
+            .to_string()
+            .contains("is not provided"));
+    }
+
+    #[test]
+    fn test_durable_nonce_transfer() {
+        let nonce_account = SolanaAddress(Pubkey::new_unique().to_string());
+        let mut params = SolanaTransactionParameters::native(
+            SolanaAddress(ALICE.to_string()),
+            SolanaAddress(BOB.to_string()),
+            1_000_000,
+            BLOCKHASH.to_string(),
+        );
+        params.nonce_account = Some(nonce_account);
+
+        let tx = SolanaTransaction::new(&params).unwrap();
+        let bytes = bincode::serialize(&Tx::new_unsigned(tx.message().unwrap())).unwrap();
+        let parsed = SolanaTransaction::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed.params, params);
+        parsed.assert_roundtrips(&bytes).unwrap();
+
+        params.nonce_authority = Some(SolanaAddress(BOB.to_string()));
+        let tx = SolanaTransaction::new(&params).unwrap();
+        let bytes = bincode::serialize(&Tx::new_unsigned(tx.message().unwrap())).unwrap();
+        let parsed = SolanaTransaction::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed.params, params);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(token_params(true).validate().is_ok());
+
+        let mut params = token_params(true);
+        params.from = SolanaAddress("invalid".to_string());
+        assert!(SolanaTransaction::new(&params).is_err());
+
+        let mut params = token_params(true);
+        params.to = SolanaAddress("invalid".to_string());
+        assert!(SolanaTransaction::new(&params).is_err());
+
+        let mut params = token_params(true);
+        params.token = Some(SolanaAddress("invalid".to_string()));
+        assert!(SolanaTransaction::new(&params).is_err());
+
+        let mut params = token_params(true);
+        params.has_token_account = None;
+        assert!(SolanaTransaction::new(&params).is_ok());
+
+        let mut params = token_params(true);
+        params.amount = 0;
+        assert!(SolanaTransaction::new(&params)
+            .unwrap_err()
+            .to_string()
+            .contains("greater than zero"));
+
+        let mut params = SolanaTransactionParameters::wrap_sol(
+            SolanaAddress(ALICE.to_string()),
+            0,
+            BLOCKHASH.to_string(),
+        )
+        .unwrap();
+        assert!(params.validate().is_err());
+        params.operation = Some(SolanaOperation::CreateNonceAccount {
+            authority: SolanaAddress(ALICE.to_string()),
+        });
+        assert!(params.validate().is_err());
+
+        let mut params = token_params(true);
+        params.blockhash = "0OIl".to_string();
+        assert!(SolanaTransaction::new(&params)
+            .unwrap_err()
+            .to_string()
+            .contains("Invalid blockhash"));
+    }
+
+    /// Feeds truncated, tampered and randomly mutated valid transactions,
+    /// and arbitrary bytes, to `from_bytes`, which must fail gracefully,
+    /// never panic
+    #[test]
+    fn test_malformed_bytes() {
+        let mut nonce_params = token_params(false);
+        nonce_params.nonce_account = Some(SolanaAddress(BOB.to_string()));
+        let mut v0_params = token_params(true);
+        v0_params.lookup_tables = Some(vec![]);
+        v0_params.compute_unit_price = Some(5);
+        let seeds = [
+            token_params(false),
+            nonce_params,
+            v0_params,
+            SolanaTransactionParameters::native(
+                SolanaAddress(ALICE.to_string()),
+                SolanaAddress(BOB.to_string()),
+                1000,
+                BLOCKHASH.to_string(),
+            ),
+        ]
+        .map(|params| {
+            let mut tx = SolanaTransaction::new(&params).unwrap();
+            tx.sign(vec![1u8; 64], 0).unwrap()
+        });
+
+        for bytes in &seeds {
+            for len in 0..bytes.len() {
+                assert!(SolanaTransaction::from_bytes(&bytes[..len]).is_err());
+            }
+            for i in 0..bytes.len() {
+                for value in [0x00, 0x01, 0x7f, 0x80, 0xff] {
+                    let mut tampered = bytes.clone();
+                    tampered[i] = value;
+                    let _ = SolanaTransaction::from_bytes(&tampered);
+                }
+            }
+        }
+
+        let mut decoded = bincode::deserialize::<Tx>(&seeds[0]).unwrap();
+        decoded.message.instructions[1].program_id_index = 42;
+        let tampered = bincode::serialize(&decoded).unwrap();
+        assert!(SolanaTransaction::from_bytes(&tampered)
+            .unwrap_err()
+            .to_string()
+            .contains("out of range"));
+
+        let mut decoded = bincode::deserialize::<Tx>(&seeds[0]).unwrap();
+        decoded.message.instructions[1].accounts.truncate(2);
+        let tampered = bincode::serialize(&decoded).unwrap();
+        assert!(SolanaTransaction::from_bytes(&tampered).is_err());
+
+        // Arbitrary bytes, a truncated v0 message and a message whose
+        // instruction indexes past its keys
+        let garbage: [Vec<u8>; 4] = [
+            vec![0xff; 512],
+            (0..=255).collect(),
+            [vec![1], vec![0; 64], vec![0x80, 1, 1]].concat(),
+            [
+                vec![0],
+                vec![1, 0, 0, 1],
+                vec![0; 32],
+                vec![0; 32],
+                vec![1, 7, 0, 0],
+            ]
+            .concat(),
+        ];
+        for bytes in garbage {
+            assert!(SolanaTransaction::from_bytes(&bytes).is_err());
+        }
+
+        // xorshift64, deterministic so that failures reproduce
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        for _ in 0..2_000 {
+            let mut bytes = seeds[next() as usize % seeds.len()].clone();
+            for _ in 0..1 + next() % 4 {
+                let i = next() as usize % bytes.len();
+                match next() % 4 {
+                    0 => bytes[i] = next() as u8,
+                    1 => bytes[i] ^= 1 << (next() % 8),
+                    2 => bytes.insert(i, next() as u8),
+                    _ => {
+                        bytes.remove(i);
+                    }
+                }
+            }
+            if let Ok(tx) = SolanaTransaction::from_bytes(&bytes) {
+                let _ = tx.to_bytes();
+                let _ = tx.to_transaction_id();
+            }
+
+            let len = next() as usize % 512;
+            let garbage: Vec<u8> = (0..len).map(|_| next() as u8).collect();
+            let _ = SolanaTransaction::from_bytes(&garbage);
+            let _ = SolanaTransaction::from_str(&bs58::encode(&garbage).into_string());
+        }
+
+        // Fields set after `new` skip validation, building used to panic on them
+        let mut tx = SolanaTransaction::new(&token_params(false)).unwrap();
+        tx.params.to = SolanaAddress("invalid".to_string());
+        assert!(tx.to_bytes().is_err());
+        let mut tx = SolanaTransaction::new(&token_params(false)).unwrap();
+        tx.params.nonce_account = Some(SolanaAddress("invalid".to_string()));
+        assert!(tx.to_bytes().is_err());
+        let mut tx = SolanaTransaction::new(&token_params(false)).unwrap();
+        tx.signature = vec![vec![1u8; 63]];
+        assert!(tx.to_transaction_id().is_err());
+    }
+
+    #[test]
+    fn test_offline_signing() {
+        let keypair = Keypair::new();
+        let mut params = token_params(false);
+        params.from = SolanaAddress(keypair.pubkey().to_string());
+        let mut tx = SolanaTransaction::new(&params).unwrap();
+
+        // on the air-gapped signer
+        let exported = tx.export_unsigned().unwrap();
+        let message = bs58::decode(&exported).into_vec().unwrap();
+        let signature = keypair.sign_message(&message);
+
+        let mut other = SolanaTransaction::new(&token_params(false)).unwrap();
+        assert!(other
+            .import_and_sign(&exported, signature.as_ref().to_vec())
+            .is_err());
+        assert!(!other.is_signed());
+
+        let bytes = tx
+            .import_and_sign(&exported, signature.as_ref().to_vec())
+            .unwrap();
+        let signed = bincode::deserialize::<Tx>(&bytes).unwrap();
+        assert!(signed.verify().is_ok());
+    }
+
+    #[test]
+    fn test_content_id() {
+        let mut tx1 = SolanaTransaction::new(&token_params(true)).unwrap();
+        let mut tx2 = SolanaTransaction::new(&token_params(true)).unwrap();
+        tx1.sign(vec![1u8; 64], 0).unwrap();
+        tx2.sign(vec![2u8; 64], 0).unwrap();
+        assert_ne!(tx1, tx2);
+        assert_eq!(tx1.content_id().unwrap(), tx2.content_id().unwrap());
+
+        let tx3 = SolanaTransaction::new(&token_params(false)).unwrap();
+        assert_ne!(tx1.content_id().unwrap(), tx3.content_id().unwrap());
+    }
+
+    #[test]
+    fn test_approval_digest() {
+        let mut tx = SolanaTransaction::new(&token_params(true)).unwrap();
+        let digest = tx.approval_digest().unwrap();
+        assert_eq!(digest.len(), 64);
+        assert_eq!(digest, tx.clone().approval_digest().unwrap());
+        assert!(tx.approval_json().unwrap().contains(
+            r#""decoded":{"amount":1000000,"decimals":6,"instruction":"TransferChecked"}"#
+        ));
+        // Pinned so that a dependency bump changing the rendering is noticed
+        assert_eq!(
+            digest,
+            "e5bbefe29ff98253bba98427d56b8b0e08f7ea21f04355e98124c289feb3ff86"
+        );
+
+        tx.params.amount += 1;
+        assert_ne!(digest, tx.approval_digest().unwrap());
+    }
 }
@@ -0,0 +1,130 @@
+//! JavaScript bindings over JSON and base58 strings, enabled by the `wasm` feature.
+
+use {
+    crate::transaction::{SolanaTransaction, SolanaTransactionParameters},
+    anychain_core::{Transaction, TransactionError},
+    solana_sdk::{
+        message::VersionedMessage, signature::Signature, transaction::VersionedTransaction,
+    },
+    std::str::FromStr,
+    wasm_bindgen::prelude::*,
+};
+
+/// Builds the unsigned message of a transfer described by `params_json`
+/// and returns it base58 encoded, ready to be signed
+#[wasm_bindgen]
+pub fn build_transfer(params_json: &str) -> Result<String, JsError> {
+    Ok(build_transfer_json(params_json)?)
+}
+
+/// Attaches the hex encoded fee payer signature to the base58 message,
+/// legacy or v0, returned by `build_transfer` and returns the base58 signed
+/// transaction. The message is kept byte for byte, other signer slots are
+/// left zeroed.
+#[wasm_bindgen]
+pub fn attach_signature(tx_b58: &str, sig_hex: &str) -> Result<String, JsError> {
+    Ok(attach_signature_hex(tx_b58, sig_hex)?)
+}
+
+/// Parses a base58 signed transaction and returns its parameters as JSON
+#[wasm_bindgen]
+pub fn parse_transaction(tx_b58: &str) -> Result<String, JsError> {
+    Ok(parse_transaction_json(tx_b58)?)
+}
+
+fn build_transfer_json(params_json: &str) -> Result<String, TransactionError> {
+    let params = serde_json::from_str::<SolanaTransactionParameters>(params_json)
+        .map_err(|e| TransactionError::Message(format!("{}", e)))?;
+    let tx = SolanaTransaction::new(&params)?;
+    Ok(bs58::encode(tx.to_bytes()?).into_string())
+}
+
+fn attach_signature_hex(tx_b58: &str, sig_hex: &str) -> Result<String, TransactionError> {
+    let msg = bs58::decode(tx_b58)
+        .into_vec()
+        .map_err(|e| TransactionError::Message(format!("{}", e)))?;
+    let message = bincode::deserialize::<VersionedMessage>(&msg)
+        .map_err(|e| TransactionError::Message(format!("{}", e)))?;
+    let sig = hex::decode(sig_hex).map_err(|e| TransactionError::Message(format!("{}", e)))?;
+    let sig = Signature::try_from(sig.as_slice())
+        .map_err(|e| TransactionError::Message(format!("{}", e)))?;
+
+    let mut signatures =
+        vec![Signature::default(); message.header().num_required_signatures as usize];
+    match signatures.first_mut() {
+        Some(slot) => *slot = sig,
+        None => {
+            return Err(TransactionError::Message(
+                "Message has no signers".to_string(),
+            ))
+        }
+    }
+    let tx = bincode::serialize(&VersionedTransaction {
+        signatures,
+        message,
+    })
+    .map_err(|e| TransactionError::Message(format!("{}", e)))?;
+    Ok(bs58::encode(tx).into_string())
+}
+
+fn parse_transaction_json(tx_b58: &str) -> Result<String, TransactionError> {
+    let tx = SolanaTransaction::from_str(tx_b58)?;
+    serde_json::to_string(&tx.params).map_err(|e| TransactionError::Message(format!("{}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sol_transfer_round_trip() {
+        let params_json = r#"{
+            "from": "8tR45MbTcEq1W4dMXnwe7KW7xqykNxnyoBQoASMtqHK",
+            "to": "DPCG5xKuxK3NCL8FTn8h2vFharp9MbYmUZeSA2eLx4RG",
+            "amount": 1000000,
+            "blockhash": "9zb7Lrb2hVgDz5oaQjTvT6gEhVnLg3wUTmxFdJeBxwzS"
+        }"#;
+
+        let msg = build_transfer_json(params_json).unwrap();
+        let tx = attach_signature_hex(&msg, &hex::encode([7u8; 64])).unwrap();
+        let parsed = parse_transaction_json(&tx).unwrap();
+
+        assert_eq!(
+            serde_json::from_str::<SolanaTransactionParameters>(&parsed).unwrap(),
+            serde_json::from_str::<SolanaTransactionParameters>(params_json).unwrap()
+        );
+        assert_eq!(
+            SolanaTransaction::from_str(&tx).unwrap().signature,
+            vec![vec![7u8; 64]]
+        );
+    }
+
+    #[test]
+    fn test_attach_signature_v0() {
+        let params_json = r#"{
+            "from": "8tR45MbTcEq1W4dMXnwe7KW7xqykNxnyoBQoASMtqHK",
+            "to": "DPCG5xKuxK3NCL8FTn8h2vFharp9MbYmUZeSA2eLx4RG",
+            "amount": 1000000,
+            "blockhash": "9zb7Lrb2hVgDz5oaQjTvT6gEhVnLg3wUTmxFdJeBxwzS",
+            "lookup_tables": [{
+                "address": "4Nd1mBQtrMJVYVfKf2PJy9NZUZdTAsp7D4xWLs4gDB4T",
+                "addresses": ["DPCG5xKuxK3NCL8FTn8h2vFharp9MbYmUZeSA2eLx4RG"]
+            }]
+        }"#;
+
+        let msg = build_transfer_json(params_json).unwrap();
+        let tx = attach_signature_hex(&msg, &hex::encode([7u8; 64])).unwrap();
+        let tx =
+            bincode::deserialize::<VersionedTransaction>(&bs58::decode(tx).into_vec().unwrap())
+                .unwrap();
+        assert!(matches!(tx.message, VersionedMessage::V0(_)));
+        assert_eq!(tx.signatures, vec![Signature::from([7u8; 64])]);
+        // The signature covers exactly the message that was built
+        assert_eq!(
+            tx.message.serialize(),
+            bs58::decode(&msg).into_vec().unwrap()
+        );
+
+        assert!(attach_signature_hex(&msg, &hex::encode([7u8; 63])).is_err());
+    }
+}
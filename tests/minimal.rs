@@ -0,0 +1,42 @@
+//! Builds and serializes transactions through the public API only, run with
+//! `cargo test --no-default-features --test minimal` to check that the
+//! transaction-building path needs none of the optional RPC dependencies.
+
+use anychain_core::Transaction;
+use anychain_solana::{SolanaAddress, SolanaTransaction, SolanaTransactionParameters};
+
+const ALICE: &str = "8tR45MbTcEq1W4dMXnwe7KW7xqykNxnyoBQoASMtqHK";
+const BOB: &str = "DPCG5xKuxK3NCL8FTn8h2vFharp9MbYmUZeSA2eLx4RG";
+const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+const BLOCKHASH: &str = "9zb7Lrb2hVgDz5oaQjTvT6gEhVnLg3wUTmxFdJeBxwzS";
+
+#[test]
+fn test_native_transfer_to_bytes() {
+    let params = SolanaTransactionParameters::native(
+        SolanaAddress(ALICE.to_string()),
+        SolanaAddress(BOB.to_string()),
+        1_000_000,
+        BLOCKHASH.to_string(),
+    );
+    let mut tx = SolanaTransaction::new(&params).unwrap();
+    assert!(!tx.to_bytes().unwrap().is_empty());
+
+    let bytes = tx.sign(vec![1u8; 64], 0).unwrap();
+    assert_eq!(SolanaTransaction::from_bytes(&bytes).unwrap(), tx);
+}
+
+#[test]
+fn test_token_transfer_to_bytes() {
+    let params = SolanaTransactionParameters::token(
+        SolanaAddress(USDC.to_string()),
+        false,
+        SolanaAddress(ALICE.to_string()),
+        SolanaAddress(BOB.to_string()),
+        1_000_000,
+        6,
+        BLOCKHASH.to_string(),
+    );
+    let mut tx = SolanaTransaction::new(&params).unwrap();
+    let bytes = tx.sign(vec![1u8; 64], 0).unwrap();
+    assert_eq!(SolanaTransaction::from_bytes(&bytes).unwrap(), tx);
+}
@@ -0,0 +1,81 @@
+//! Known signed transactions and the params `from_bytes` decodes them into.
+//! To add a vector, sign a transaction, append its base58 form and the JSON
+//! of its params to `VECTORS`; fields left out of the JSON are `None`.
+
+use anychain_core::Transaction;
+use anychain_solana::{SolanaTransaction, SolanaTransactionParameters};
+use std::str::FromStr;
+
+struct Vector {
+    name: &'static str,
+    /// The signed transaction in base58
+    tx: &'static str,
+    /// The expected params as JSON
+    params: &'static str,
+    /// Whether `to_bytes` rebuilds `tx` exactly
+    roundtrips: bool,
+}
+
+const VECTORS: &[Vector] = &[
+    Vector {
+        name: "sol transfer",
+        tx: "4NctDfUTDUGLW4mgpwAKCHdZ9ZbsJyLjRNXKCh1uT8phaoFdFdAmVvPj16u4MxznoNkdF3sCbMpK9RYpv8d8QAi4GnH5T4979rbuDjjPBaJQvTinrJZ3FGD1no6vFTawgVvujcaKQTZAjVn5ZLr34DjK8DDqkTuG4geP7ipWGSG95neYnQBW1D6fkFLTcJWhD4h4HAbadEzU4qrkQ6xLYkiDBtSRDP4LcHSuVQcx2AULTC7y4k2bgjobUwjhmjwGRazF2qYWJbGcu878vbEqkTN4F1ZAZPPvBuq9H",
+        params: r#"{
+            "from": "GmaDrppBC7P5ARKV8g3djiwP89vz1jLK23V2GBjuAEGB",
+            "to": "DPCG5xKuxK3NCL8FTn8h2vFharp9MbYmUZeSA2eLx4RG",
+            "amount": 1000000,
+            "blockhash": "9zb7Lrb2hVgDz5oaQjTvT6gEhVnLg3wUTmxFdJeBxwzS"
+        }"#,
+        roundtrips: true,
+    },
+    // The parser reports the destination token account as `to`, whereas
+    // the builder derives the destination from the recipient's wallet
+    Vector {
+        name: "token transfer",
+        tx: "V71Wfo7cHsU9tr2Aix1arTYvCxtMPRMYVgfDK8aWr93c5LfjaAUK6sVHZSWYPCy991Xjp6rMryBymiumbfJkbV59EgepwbFGzdqS68dCUAEHN7WETn3bv6dDfFLYA6BvtbMgDAsY6t7JD4kLM9zWATM337iuueAjycSHsgG1uVhEoWKtQN8X7Gyg5XssojA2A8ywNeiaSvrQwukStwYnbQuwzcSVjFjkQSq6sY8YiAkruVu7gStKNX9CShRbLuBribuckvDFcsSpc5w1HeF2w6rb5hZ9jZGfWqmcdnoneVvnBcHW7WKBDvwfWCXecFZRNnPwG8dDsCUCW8BJX4iLouGN9QDyoLqTm1Ga4qNXJbuEVPDvZ7K7PZP4xuAd",
+        params: r#"{
+            "token": "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v",
+            "has_token_account": true,
+            "decimals": 6,
+            "from": "GmaDrppBC7P5ARKV8g3djiwP89vz1jLK23V2GBjuAEGB",
+            "to": "9vYZcQaDt25RvhRcZNUCTUrofnGU2P23ruGx8GWLMvnp",
+            "amount": 2500000,
+            "blockhash": "9zb7Lrb2hVgDz5oaQjTvT6gEhVnLg3wUTmxFdJeBxwzS"
+        }"#,
+        roundtrips: false,
+    },
+    Vector {
+        name: "create ata and token transfer",
+        tx: "AAugChZFnKZV1aNUVFYBnFz7WTfenxwJpuHAfxXWk7TDJyA1XkxfQi7kpqSCZxG186trM7X5KnDRbVfASP82cz2wieKcNMfMabK3xvNfv889p37TadXPVPXRB8pVpPZS2UVFrLkYYvgkMHT6cjKntpVLpC3BerTVxDPFhibF7xZV2P1r3djn7hQBUE1oGk4QnKyBd74ZgZco6ezChfYWGwfuJwM9vCYEnNb6odQ4DZU2BzLdEgXeiw6JkB3UNcXTU58uCi7Ri6oCkgCaJ9u2fCsHJxytnPu2bv2xSopRRquFFgYt4LAjruJkzXu1Bo64VaCR4VXrZ3x4P45WGoCgQUiMrT9vCfrrZ4caboLidH7d62VtzCwxvFcb2naXvoGkQoST25HoH7TXnWYQ7AsSd37bLsriF4eEN1snJwvq3UVW5ds5rLAKKKhiR3nJLXQ9W7S9BwhcEjhk9BczKg6YCkp2rixQZWoGhZnxmn2c5jMP71PbrZS5STd4TSRDkkTWBdbBYh2nmrFQH",
+        params: r#"{
+            "token": "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v",
+            "has_token_account": false,
+            "decimals": 6,
+            "from": "GmaDrppBC7P5ARKV8g3djiwP89vz1jLK23V2GBjuAEGB",
+            "to": "DPCG5xKuxK3NCL8FTn8h2vFharp9MbYmUZeSA2eLx4RG",
+            "amount": 1000000,
+            "blockhash": "9zb7Lrb2hVgDz5oaQjTvT6gEhVnLg3wUTmxFdJeBxwzS"
+        }"#,
+        roundtrips: true,
+    },
+];
+
+#[test]
+fn test_vectors() {
+    for vector in VECTORS {
+        let tx = SolanaTransaction::from_str(vector.tx)
+            .unwrap_or_else(|e| panic!("{}: {}", vector.name, e));
+        let params = SolanaTransactionParameters::from_str(vector.params)
+            .unwrap_or_else(|e| panic!("{}: {}", vector.name, e));
+        assert_eq!(tx.params, params, "{}", vector.name);
+        assert!(tx.is_signed(), "{}", vector.name);
+
+        let bytes = bs58::decode(vector.tx).into_vec().unwrap();
+        assert_eq!(
+            tx.to_bytes().unwrap() == bytes,
+            vector.roundtrips,
+            "{}",
+            vector.name
+        );
+    }
+}